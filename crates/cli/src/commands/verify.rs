@@ -242,7 +242,7 @@ fn validate_wasm(
 	source_json: Option<SourceJson>,
 ) -> bool {
 	let mut wasm_bytes = Vec::new();
-	if !wasm_file.read_to_end(&mut wasm_bytes).is_ok() {
+	if wasm_file.read_to_end(&mut wasm_bytes).is_err() {
 		return false;
 	}
 
@@ -0,0 +1,322 @@
+#![doc = include_str!("../README.md")]
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+	Data, DeriveInput, Field, Fields, GenericArgument, LitStr, PathArguments, Type,
+	parse_macro_input, spanned::Spanned,
+};
+
+/// Implements `aidoku::imports::html::FromElement` for a struct with named fields.
+///
+/// See the crate documentation for the supported `html` field attributes.
+#[proc_macro_derive(FromElement, attributes(html))]
+pub fn derive_from_element(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	match from_element(input) {
+		Ok(tokens) => tokens.into(),
+		Err(err) => err.to_compile_error().into(),
+	}
+}
+
+fn from_element(input: DeriveInput) -> syn::Result<TokenStream2> {
+	let Data::Struct(data) = &input.data else {
+		return Err(syn::Error::new(
+			input.ident.span(),
+			"FromElement can only be derived for structs",
+		));
+	};
+	let Fields::Named(fields) = &data.fields else {
+		return Err(syn::Error::new(
+			data.fields.span(),
+			"FromElement can only be derived for structs with named fields",
+		));
+	};
+
+	let mut values = Vec::new();
+	for field in &fields.named {
+		let name = &field.ident;
+		let value = field_value(field, FieldArgs::parse(field)?)?;
+		values.push(quote! { #name: #value });
+	}
+
+	let ident = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	Ok(quote! {
+		impl #impl_generics ::aidoku::imports::html::FromElement for #ident #ty_generics #where_clause {
+			fn from_element(
+				element: &::aidoku::imports::html::Element,
+			) -> ::core::result::Result<Self, ::aidoku::imports::html::HtmlError> {
+				::core::result::Result::Ok(Self {
+					#(#values),*
+				})
+			}
+		}
+	})
+}
+
+/// Where the value of a field comes from.
+enum Extract {
+	Text,
+	OwnText,
+	Html,
+	OuterHtml,
+	Attr(LitStr),
+	Nested,
+}
+
+/// The options of a field's `html` attribute.
+struct FieldArgs {
+	select: Option<LitStr>,
+	extract: Extract,
+	trim: bool,
+	parse: bool,
+	skip: bool,
+}
+
+impl FieldArgs {
+	fn parse(field: &Field) -> syn::Result<Self> {
+		let mut select = None;
+		let mut extract = None;
+		let mut trim = false;
+		let mut parse = false;
+		let mut skip = false;
+		let mut found = false;
+
+		for attr in field
+			.attrs
+			.iter()
+			.filter(|attr| attr.path().is_ident("html"))
+		{
+			found = true;
+			attr.parse_nested_meta(|meta| {
+				let mut set_extract = |value| {
+					if extract.replace(value).is_some() {
+						Err(meta.error(
+							"only one of `text`, `own_text`, `html`, `outer_html`, `attr` or `nested` can be used",
+						))
+					} else {
+						Ok(())
+					}
+				};
+				if meta.path.is_ident("select") {
+					select = Some(meta.value()?.parse::<LitStr>()?);
+				} else if meta.path.is_ident("text") {
+					set_extract(Extract::Text)?;
+				} else if meta.path.is_ident("own_text") {
+					set_extract(Extract::OwnText)?;
+				} else if meta.path.is_ident("html") {
+					set_extract(Extract::Html)?;
+				} else if meta.path.is_ident("outer_html") {
+					set_extract(Extract::OuterHtml)?;
+				} else if meta.path.is_ident("attr") {
+					let name = meta.value()?.parse::<LitStr>()?;
+					set_extract(Extract::Attr(name))?;
+				} else if meta.path.is_ident("nested") {
+					set_extract(Extract::Nested)?;
+				} else if meta.path.is_ident("trim") {
+					trim = true;
+				} else if meta.path.is_ident("parse") {
+					parse = true;
+				} else if meta.path.is_ident("skip") {
+					skip = true;
+				} else {
+					return Err(meta.error("unknown html attribute option"));
+				}
+				Ok(())
+			})?;
+		}
+
+		if !found {
+			return Err(syn::Error::new(
+				field.span(),
+				"missing `#[html(...)]` attribute, use `#[html(skip)]` to use the default value",
+			));
+		}
+		let extract = extract.unwrap_or(Extract::Text);
+		if matches!(extract, Extract::Nested) && (trim || parse) {
+			return Err(syn::Error::new(
+				field.span(),
+				"`trim` and `parse` can't be used with `nested`",
+			));
+		}
+		Ok(Self {
+			select,
+			extract,
+			trim,
+			parse,
+			skip,
+		})
+	}
+
+	/// A description of the extracted value for errors, like `a[href]` for an attribute.
+	fn description(&self) -> String {
+		let selector = self
+			.select
+			.as_ref()
+			.map(|select| select.value())
+			.unwrap_or_else(|| ":scope".into());
+		match &self.extract {
+			Extract::Attr(name) => format!("{selector}[{}]", name.value()),
+			_ => selector,
+		}
+	}
+
+	/// An expression for the value of the element in `element`, as an `Option<String>`.
+	fn value(&self) -> TokenStream2 {
+		let value = match &self.extract {
+			Extract::Text => quote! { element.text() },
+			Extract::OwnText => quote! { element.own_text() },
+			Extract::Html => quote! { element.html() },
+			Extract::OuterHtml => quote! { element.outer_html() },
+			Extract::Attr(name) => quote! { element.attr(#name) },
+			Extract::Nested => unreachable!(),
+		};
+		if self.trim {
+			quote! { #value.map(|value| ::aidoku::alloc::String::from(value.trim())) }
+		} else {
+			value
+		}
+	}
+
+	/// An expression converting the string in `value` to the field's type, as a `Result`.
+	fn convert(&self) -> TokenStream2 {
+		let description = self.description();
+		if self.parse {
+			quote! {
+				value.trim().parse().map_err(|_| {
+					::aidoku::imports::html::HtmlError::InvalidValue(#description.into())
+				})
+			}
+		} else {
+			quote! { ::core::result::Result::<_, ::aidoku::imports::html::HtmlError>::Ok(value) }
+		}
+	}
+
+	/// An expression extracting the value from the element in `element`, failing if it's missing.
+	fn required(&self) -> TokenStream2 {
+		if matches!(self.extract, Extract::Nested) {
+			return quote! { ::aidoku::imports::html::FromElement::from_element(element) };
+		}
+		let value = self.value();
+		let convert = self.convert();
+		let description = self.description();
+		quote! {
+			match #value {
+				::core::option::Option::Some(value) => #convert,
+				::core::option::Option::None => ::core::result::Result::Err(
+					::aidoku::imports::html::HtmlError::MissingValue(#description.into()),
+				),
+			}
+		}
+	}
+
+	/// An expression extracting the value from the element in `element` as an `Option`.
+	fn optional(&self) -> TokenStream2 {
+		if matches!(self.extract, Extract::Nested) {
+			return quote! {
+				::aidoku::imports::html::FromElement::from_element(element)
+					.map(::core::option::Option::Some)
+			};
+		}
+		let value = self.value();
+		let convert = self.convert();
+		quote! {
+			match #value {
+				::core::option::Option::Some(value) => (#convert).map(::core::option::Option::Some),
+				::core::option::Option::None => ::core::result::Result::Ok(::core::option::Option::None),
+			}
+		}
+	}
+}
+
+/// The kind of value a field holds, from its type.
+enum FieldKind {
+	Required,
+	Option,
+	Vec,
+}
+
+impl FieldKind {
+	fn of(ty: &Type) -> Self {
+		let Type::Path(path) = ty else {
+			return Self::Required;
+		};
+		let Some(segment) = path.path.segments.last() else {
+			return Self::Required;
+		};
+		// only match wrappers with a single type argument, like `Option<T>`
+		let PathArguments::AngleBracketed(args) = &segment.arguments else {
+			return Self::Required;
+		};
+		if args.args.len() != 1 || !matches!(args.args[0], GenericArgument::Type(_)) {
+			return Self::Required;
+		}
+		if segment.ident == "Option" {
+			Self::Option
+		} else if segment.ident == "Vec" {
+			Self::Vec
+		} else {
+			Self::Required
+		}
+	}
+}
+
+/// An expression for the value of a field, which returns early on errors.
+fn field_value(field: &Field, args: FieldArgs) -> syn::Result<TokenStream2> {
+	if args.skip {
+		return Ok(quote! { ::core::default::Default::default() });
+	}
+	let value = match FieldKind::of(&field.ty) {
+		FieldKind::Vec => {
+			let Some(select) = &args.select else {
+				return Err(syn::Error::new(
+					field.span(),
+					"`Vec` fields need a `select` selector",
+				));
+			};
+			let required = args.required();
+			quote! {
+				match element.select(#select) {
+					::core::option::Option::Some(elements) => elements
+						.map(|element| {
+							let element = &element;
+							#required
+						})
+						.collect::<::core::result::Result<::aidoku::alloc::Vec<_>, _>>()?,
+					::core::option::Option::None => ::aidoku::alloc::Vec::new(),
+				}
+			}
+		}
+		FieldKind::Option => {
+			let optional = args.optional();
+			match &args.select {
+				Some(select) => quote! {
+					match element.select_first(#select) {
+						::core::option::Option::Some(element) => {
+							let element = &element;
+							(#optional)?
+						}
+						::core::option::Option::None => ::core::option::Option::None,
+					}
+				},
+				None => quote! { (#optional)? },
+			}
+		}
+		FieldKind::Required => {
+			let required = args.required();
+			match &args.select {
+				Some(select) => quote! {
+					{
+						let element = &element.select_first(#select).ok_or_else(|| {
+							::aidoku::imports::html::HtmlError::MissingElement(#select.into())
+						})?;
+						(#required)?
+					}
+				},
+				None => quote! { (#required)? },
+			}
+		}
+	};
+	Ok(value)
+}
@@ -0,0 +1,332 @@
+//! Validation of source results against the rules that Aidoku relies on.
+//!
+//! These checks are intended to be used in tests, so mistakes that would otherwise only
+//! surface as odd behavior in the app are caught early.
+//!
+//! # Example
+//! ```ignore
+//! use aidoku::helpers::contract::assert_valid;
+//!
+//! #[aidoku_test]
+//! fn search() {
+//!     let result = Source::new().get_search_manga_list(None, 1, Vec::new()).unwrap();
+//!     assert_valid(&result);
+//! }
+//! ```
+//!
+//! Errors fail the test, while warnings are printed to its output.
+extern crate alloc;
+
+use crate::{Chapter, Manga, MangaPageResult, Page, PageContent};
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+use hashbrown::HashSet;
+
+/// The maximum number of tags that a manga can have.
+pub const MAX_TAGS: usize = 255;
+
+/// How severe a contract violation is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+	/// Something that's likely a mistake, but won't break the app.
+	Warning,
+	/// Something that the app doesn't handle correctly.
+	Error,
+}
+
+/// A broken rule found in a source result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+	pub severity: Severity,
+	/// The path to the offending field, e.g. `chapters[2].key`.
+	pub path: String,
+	pub message: String,
+}
+
+impl Violation {
+	fn error(path: impl Into<String>, message: impl Into<String>) -> Self {
+		Self {
+			severity: Severity::Error,
+			path: path.into(),
+			message: message.into(),
+		}
+	}
+
+	fn warning(path: impl Into<String>, message: impl Into<String>) -> Self {
+		Self {
+			severity: Severity::Warning,
+			path: path.into(),
+			message: message.into(),
+		}
+	}
+}
+
+impl fmt::Display for Violation {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let severity = match self.severity {
+			Severity::Warning => "warning",
+			Severity::Error => "error",
+		};
+		if self.path.is_empty() {
+			write!(f, "{severity}: {}", self.message)
+		} else {
+			write!(f, "{severity}: {}: {}", self.path, self.message)
+		}
+	}
+}
+
+/// A source result that can be checked for contract violations.
+pub trait Validate {
+	/// Returns all contract violations found in the value.
+	fn validate(&self) -> Vec<Violation> {
+		let mut violations = Vec::new();
+		self.validate_at("", &mut violations);
+		violations
+	}
+
+	/// Adds contract violations to the given list, prefixing field paths with `path`.
+	fn validate_at(&self, path: &str, violations: &mut Vec<Violation>);
+}
+
+/// Panics if the value has any contract errors.
+///
+/// Warnings don't fail the check, but are printed so they show up in the test's output.
+pub fn assert_valid<T: Validate + ?Sized>(value: &T) {
+	assert_valid_with(value, print_warning);
+}
+
+/// Panics if the value has any contract errors, passing each warning to `warn` first.
+pub fn assert_valid_with<T, F>(value: &T, mut warn: F)
+where
+	T: Validate + ?Sized,
+	F: FnMut(&Violation),
+{
+	let mut errors = Vec::new();
+	for violation in value.validate() {
+		match violation.severity {
+			Severity::Warning => warn(&violation),
+			Severity::Error => errors.push(format!("{violation}")),
+		}
+	}
+	if !errors.is_empty() {
+		panic!("source contract violated:\n{}", errors.join("\n"));
+	}
+}
+
+/// Prints a warning to the source's output, or to stderr when run natively.
+#[allow(unused_variables)]
+fn print_warning(violation: &Violation) {
+	#[cfg(all(target_family = "wasm", feature = "imports"))]
+	crate::prelude::println!("{violation}");
+	#[cfg(not(target_family = "wasm"))]
+	{
+		extern crate std;
+		std::eprintln!("{violation}");
+	}
+}
+
+fn field(path: &str, name: &str) -> String {
+	if path.is_empty() {
+		name.into()
+	} else {
+		format!("{path}.{name}")
+	}
+}
+
+fn index(path: &str, name: &str, idx: usize) -> String {
+	format!("{}[{idx}]", field(path, name))
+}
+
+/// Checks that a url has a scheme, and a host if it's an http url.
+fn is_absolute_url(url: &str) -> bool {
+	let Some((scheme, rest)) = url.split_once(':') else {
+		return false;
+	};
+	let valid_scheme = scheme
+		.chars()
+		.next()
+		.is_some_and(|c| c.is_ascii_alphabetic())
+		&& scheme
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+	if !valid_scheme {
+		return false;
+	}
+	if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") {
+		rest.strip_prefix("//")
+			.and_then(|rest| rest.split(['/', '?', '#']).next())
+			.is_some_and(|host| !host.is_empty())
+	} else {
+		!rest.is_empty()
+	}
+}
+
+fn check_url(path: &str, name: &str, url: Option<&str>, violations: &mut Vec<Violation>) {
+	if let Some(url) = url
+		&& !is_absolute_url(url)
+	{
+		violations.push(Violation::error(
+			field(path, name),
+			format!("`{url}` is not an absolute url"),
+		));
+	}
+}
+
+fn check_not_empty(path: &str, name: &str, value: &str, violations: &mut Vec<Violation>) {
+	if value.trim().is_empty() {
+		violations.push(Violation::error(field(path, name), "must not be empty"));
+	}
+}
+
+impl Validate for Manga {
+	fn validate_at(&self, path: &str, violations: &mut Vec<Violation>) {
+		check_not_empty(path, "key", &self.key, violations);
+		check_not_empty(path, "title", &self.title, violations);
+		check_url(path, "cover", self.cover.as_deref(), violations);
+		check_url(path, "url", self.url.as_deref(), violations);
+		if let Some(tags) = &self.tags {
+			if tags.len() > MAX_TAGS {
+				violations.push(Violation::error(
+					field(path, "tags"),
+					format!(
+						"has {} tags, but at most {MAX_TAGS} are allowed",
+						tags.len()
+					),
+				));
+			}
+			for (idx, tag) in tags.iter().enumerate() {
+				if tag.trim().is_empty() {
+					violations.push(Violation::warning(index(path, "tags", idx), "is empty"));
+				}
+			}
+		}
+		if let Some(chapters) = &self.chapters {
+			chapters.validate_at(&field(path, "chapters"), violations);
+		}
+	}
+}
+
+impl Validate for Chapter {
+	fn validate_at(&self, path: &str, violations: &mut Vec<Violation>) {
+		check_not_empty(path, "key", &self.key, violations);
+		check_url(path, "url", self.url.as_deref(), violations);
+		check_url(path, "thumbnail", self.thumbnail.as_deref(), violations);
+		for (name, number) in [
+			("chapter_number", self.chapter_number),
+			("volume_number", self.volume_number),
+		] {
+			if let Some(number) = number
+				&& (!number.is_finite() || number < 0.0)
+			{
+				violations.push(Violation::warning(
+					field(path, name),
+					format!("{number} is not a valid number"),
+				));
+			}
+		}
+	}
+}
+
+/// Validates a list of chapters, including that chapter keys are unique.
+impl Validate for [Chapter] {
+	fn validate_at(&self, path: &str, violations: &mut Vec<Violation>) {
+		let mut keys = HashSet::new();
+		for (idx, chapter) in self.iter().enumerate() {
+			let chapter_path = format!("{path}[{idx}]");
+			chapter.validate_at(&chapter_path, violations);
+			if !keys.insert(chapter.key.as_str()) {
+				violations.push(Violation::error(
+					field(&chapter_path, "key"),
+					format!("duplicate chapter key `{}`", chapter.key),
+				));
+			}
+		}
+	}
+}
+
+impl Validate for Vec<Chapter> {
+	fn validate_at(&self, path: &str, violations: &mut Vec<Violation>) {
+		self.as_slice().validate_at(path, violations);
+	}
+}
+
+impl Validate for MangaPageResult {
+	fn validate_at(&self, path: &str, violations: &mut Vec<Violation>) {
+		if self.has_next_page && self.entries.is_empty() {
+			violations.push(Violation::warning(
+				field(path, "has_next_page"),
+				"is true, but the page has no entries",
+			));
+		}
+		let mut keys = HashSet::new();
+		for (idx, manga) in self.entries.iter().enumerate() {
+			let manga_path = index(path, "entries", idx);
+			manga.validate_at(&manga_path, violations);
+			if !keys.insert(manga.key.as_str()) {
+				violations.push(Violation::warning(
+					field(&manga_path, "key"),
+					format!("duplicate manga key `{}`", manga.key),
+				));
+			}
+		}
+	}
+}
+
+impl Validate for Page {
+	fn validate_at(&self, path: &str, violations: &mut Vec<Violation>) {
+		let content_path = field(path, "content");
+		match &self.content {
+			PageContent::Url(url, _) => {
+				if !is_absolute_url(url) {
+					violations.push(Violation::error(
+						content_path,
+						format!("`{url}` is not an absolute url"),
+					));
+				}
+			}
+			PageContent::Text(text) => {
+				if text.trim().is_empty() {
+					violations.push(Violation::warning(content_path, "text is empty"));
+				}
+			}
+			PageContent::Zip(url, file_path) => {
+				if !is_absolute_url(url) {
+					violations.push(Violation::error(
+						content_path.clone(),
+						format!("zip `{url}` is not an absolute url"),
+					));
+				}
+				if file_path.is_empty() {
+					violations.push(Violation::error(content_path, "zip file path is empty"));
+				}
+			}
+			#[cfg(feature = "imports")]
+			PageContent::Image(_) => {}
+		}
+		check_url(path, "thumbnail", self.thumbnail.as_deref(), violations);
+		if !self.has_description && self.description.is_some() {
+			violations.push(Violation::warning(
+				field(path, "description"),
+				"is set, but will be ignored because has_description is false",
+			));
+		}
+	}
+}
+
+/// Validates the pages of a chapter, which should not be empty.
+impl Validate for [Page] {
+	fn validate_at(&self, path: &str, violations: &mut Vec<Violation>) {
+		if self.is_empty() {
+			violations.push(Violation::warning(path, "chapter has no pages"));
+		}
+		for (idx, page) in self.iter().enumerate() {
+			page.validate_at(&format!("{path}[{idx}]"), violations);
+		}
+	}
+}
+
+impl Validate for Vec<Page> {
+	fn validate_at(&self, path: &str, violations: &mut Vec<Violation>) {
+		self.as_slice().validate_at(path, violations);
+	}
+}
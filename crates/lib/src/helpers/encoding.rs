@@ -0,0 +1,111 @@
+//! Decoding of text in legacy encodings, like Shift_JIS, GBK or windows-1251.
+//!
+//! The encoding is detected in the same order as browsers: a byte order mark, then the charset
+//! of the `Content-Type` header, and finally a `<meta charset>` tag, defaulting to UTF-8.
+//!
+//! Common encodings are re-exported as constants, and others can be looked up with [for_label].
+use crate::alloc::String;
+pub use encoding_rs::{
+	BIG5, EUC_JP, EUC_KR, Encoding, GB18030, GBK, KOI8_R, SHIFT_JIS, UTF_8, WINDOWS_1251,
+	WINDOWS_1252,
+};
+use encoding_rs::{UTF_16BE, UTF_16LE, X_USER_DEFINED};
+
+/// The number of bytes that are scanned for a `<meta charset>` tag.
+const META_SCAN_LEN: usize = 1024;
+
+/// Returns the encoding for the given label, e.g. `shift_jis` or `euc-kr`.
+pub fn for_label(label: &str) -> Option<&'static Encoding> {
+	Encoding::for_label(label.trim().as_bytes())
+}
+
+/// Returns the encoding from the charset parameter of a `Content-Type` header value.
+pub fn from_content_type(content_type: &str) -> Option<&'static Encoding> {
+	content_type.split(';').skip(1).find_map(|param| {
+		let (name, value) = param.split_once('=')?;
+		if !name.trim().eq_ignore_ascii_case("charset") {
+			return None;
+		}
+		for_label(value.trim().trim_matches(['"', '\'']))
+	})
+}
+
+/// Returns the encoding declared by a `<meta>` tag near the start of an HTML document.
+///
+/// Both `<meta charset="...">` and `<meta http-equiv="Content-Type" content="...; charset=...">`
+/// are supported.
+pub fn from_meta(html: &[u8]) -> Option<&'static Encoding> {
+	let html = &html[..html.len().min(META_SCAN_LEN)];
+	let mut rest = html;
+	while let Some(start) = find_ignore_case(rest, b"<meta") {
+		let tag = &rest[start + 5..];
+		let end = tag.iter().position(|&b| b == b'>').unwrap_or(tag.len());
+		let tag = &tag[..end];
+		rest = &rest[start + 5 + end..];
+
+		let Some(idx) = find_ignore_case(tag, b"charset") else {
+			continue;
+		};
+		let value = tag[idx + 7..].trim_ascii_start();
+		let Some(value) = value.strip_prefix(b"=") else {
+			continue;
+		};
+		let value = value.trim_ascii_start();
+		let value = value
+			.strip_prefix(b"\"")
+			.or_else(|| value.strip_prefix(b"'"))
+			.unwrap_or(value);
+		let len = value
+			.iter()
+			.position(|b| matches!(b, b'"' | b'\'' | b';' | b'/') || b.is_ascii_whitespace())
+			.unwrap_or(value.len());
+		if let Some(encoding) = Encoding::for_label(&value[..len]) {
+			// a document declaring utf-16 in ascii can't actually be utf-16
+			return Some(match encoding {
+				encoding if encoding == UTF_16LE || encoding == UTF_16BE => UTF_8,
+				encoding if encoding == X_USER_DEFINED => WINDOWS_1252,
+				encoding => encoding,
+			});
+		}
+	}
+	None
+}
+
+/// Detects the encoding of the given data.
+///
+/// Without a byte order mark or a charset in the content type, valid UTF-8 is assumed to be
+/// UTF-8 regardless of any `<meta charset>` tag, since text that was already decoded may still
+/// contain its original tag.
+pub fn detect(data: &[u8], content_type: Option<&str>) -> &'static Encoding {
+	if let Some((encoding, _)) = Encoding::for_bom(data) {
+		return encoding;
+	}
+	if let Some(encoding) = content_type.and_then(from_content_type) {
+		return encoding;
+	}
+	if core::str::from_utf8(data).is_ok() {
+		return UTF_8;
+	}
+	from_meta(data).unwrap_or(UTF_8)
+}
+
+/// Decodes the given data, detecting the encoding with [detect].
+///
+/// Invalid sequences are replaced with U+FFFD.
+pub fn decode(data: &[u8], content_type: Option<&str>) -> String {
+	decode_with(data, detect(data, content_type))
+}
+
+/// Decodes the given data with the given encoding.
+///
+/// A byte order mark takes precedence over the given encoding, and invalid sequences
+/// are replaced with U+FFFD.
+pub fn decode_with(data: &[u8], encoding: &'static Encoding) -> String {
+	encoding.decode(data).0.into_owned()
+}
+
+fn find_ignore_case(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+	haystack
+		.windows(needle.len())
+		.position(|window| window.eq_ignore_ascii_case(needle))
+}
@@ -0,0 +1,244 @@
+//! A client for sending GraphQL queries.
+//!
+//! Queries are sent as JSON with [Request::post], and the `data` field of the response is
+//! deserialized into the target type. Errors returned by the server become an
+//! [AidokuError::GraphqlError] with the server's messages.
+//!
+//! # Example
+//! ```ignore
+//! use aidoku::helpers::graphql::Query;
+//!
+//! #[derive(serde::Serialize)]
+//! struct Variables {
+//!     id: i32,
+//! }
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Data {
+//!     manga: Manga,
+//! }
+//!
+//! let data: Data = Query::new("query ($id: Int) { manga(id: $id) { title } }")
+//!     .variables(Variables { id: 1 })
+//!     .send("https://example.com/graphql")?;
+//! ```
+extern crate alloc;
+
+use crate::{
+	AidokuError,
+	imports::net::{Request, RequestError},
+};
+use alloc::{string::String, vec::Vec};
+use serde::{
+	Deserialize, Serialize,
+	de::{DeserializeOwned, IgnoredAny},
+};
+
+/// The error message returned by servers that don't know a persisted query's hash.
+const PERSISTED_QUERY_NOT_FOUND: &str = "PersistedQueryNotFound";
+/// The error code returned in the `extensions` of the error by Apollo servers instead.
+const PERSISTED_QUERY_NOT_FOUND_CODE: &str = "PERSISTED_QUERY_NOT_FOUND";
+
+/// A GraphQL query with its variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query<V = ()> {
+	query: Option<String>,
+	operation_name: Option<String>,
+	variables: Option<V>,
+	persisted_hash: Option<String>,
+	headers: Vec<(String, String)>,
+}
+
+impl Query {
+	/// Creates a query from its document.
+	pub fn new<T: Into<String>>(query: T) -> Self {
+		Self {
+			query: Some(query.into()),
+			operation_name: None,
+			variables: None,
+			persisted_hash: None,
+			headers: Vec::new(),
+		}
+	}
+
+	/// Creates a persisted query, which is sent as the SHA-256 hash of its document
+	/// instead of the document itself.
+	pub fn persisted<T: Into<String>>(sha256_hash: T) -> Self {
+		Self {
+			query: None,
+			operation_name: None,
+			variables: None,
+			persisted_hash: Some(sha256_hash.into()),
+			headers: Vec::new(),
+		}
+	}
+}
+
+impl<V: Serialize> Query<V> {
+	/// Sets the variables of the query.
+	pub fn variables<W: Serialize>(self, variables: W) -> Query<W> {
+		Query {
+			query: self.query,
+			operation_name: self.operation_name,
+			variables: Some(variables),
+			persisted_hash: self.persisted_hash,
+			headers: self.headers,
+		}
+	}
+
+	/// Sets the name of the operation to run, for documents with multiple operations.
+	pub fn operation_name<T: Into<String>>(mut self, name: T) -> Self {
+		self.operation_name = Some(name.into());
+		self
+	}
+
+	/// Adds a header to the requests that send the query.
+	pub fn header<T: Into<String>>(mut self, key: T, value: T) -> Self {
+		self.headers.push((key.into(), value.into()));
+		self
+	}
+
+	/// Sends the SHA-256 hash of the query document instead of the document.
+	///
+	/// If the server doesn't know the hash, the query is sent again with the document
+	/// so the server can store it.
+	pub fn persisted_hash<T: Into<String>>(mut self, sha256_hash: T) -> Self {
+		self.persisted_hash = Some(sha256_hash.into());
+		self
+	}
+
+	/// Returns the JSON request body for the query.
+	///
+	/// The document is left out of persisted queries unless `include_query` is true.
+	pub fn body(&self, include_query: bool) -> Result<Vec<u8>, AidokuError> {
+		let body = Body {
+			query: self
+				.query
+				.as_deref()
+				.filter(|_| include_query || self.persisted_hash.is_none()),
+			operation_name: self.operation_name.as_deref(),
+			variables: self.variables.as_ref(),
+			extensions: self.persisted_hash.as_deref().map(|hash| Extensions {
+				persisted_query: PersistedQuery {
+					version: 1,
+					sha256_hash: hash,
+				},
+			}),
+		};
+		Ok(serde_json::to_vec(&body)?)
+	}
+
+	/// Creates a POST request to the given url that sends the query.
+	pub fn request<T: AsRef<str>>(&self, url: T) -> Result<Request, AidokuError> {
+		self.request_with(url.as_ref(), false)
+	}
+
+	fn request_with(&self, url: &str, include_query: bool) -> Result<Request, AidokuError> {
+		let mut request = Request::post(url)?
+			.header("Content-Type", "application/json")
+			.header("Accept", "application/json")
+			.body(self.body(include_query)?);
+		for (key, value) in &self.headers {
+			request.set_header(key, value);
+		}
+		Ok(request)
+	}
+
+	/// Sends the query to the given url and returns its data.
+	pub fn send<T: DeserializeOwned, U: AsRef<str>>(&self, url: U) -> Result<T, AidokuError> {
+		let url = url.as_ref();
+		let mut data = self.request_with(url, false)?.data()?;
+		if self.should_resend(&data) {
+			data = self.request_with(url, true)?.data()?;
+		}
+		parse_response(&data)
+	}
+
+	/// Returns true if the response body says that the server doesn't know the persisted
+	/// query's hash, and the query should be sent again with its document.
+	///
+	/// The error is recognized by a `PersistedQueryNotFound` message, or a
+	/// `PERSISTED_QUERY_NOT_FOUND` code in the error's extensions.
+	pub fn should_resend(&self, data: &[u8]) -> bool {
+		if self.query.is_none() || self.persisted_hash.is_none() {
+			return false;
+		}
+		let Ok(response) = serde_json::from_slice::<Response<IgnoredAny>>(data) else {
+			return false;
+		};
+		response.errors.iter().any(|error| {
+			error.message == PERSISTED_QUERY_NOT_FOUND
+				|| error
+					.extensions
+					.as_ref()
+					.and_then(|extensions| extensions.code.as_deref())
+					== Some(PERSISTED_QUERY_NOT_FOUND_CODE)
+		})
+	}
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Body<'a, V> {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	query: Option<&'a str>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	operation_name: Option<&'a str>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	variables: Option<&'a V>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	extensions: Option<Extensions<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Extensions<'a> {
+	persisted_query: PersistedQuery<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PersistedQuery<'a> {
+	version: u8,
+	sha256_hash: &'a str,
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+	data: Option<T>,
+	#[serde(default)]
+	errors: Vec<Error>,
+}
+
+#[derive(Deserialize)]
+struct Error {
+	message: String,
+	#[serde(default)]
+	extensions: Option<ErrorExtensions>,
+}
+
+#[derive(Deserialize)]
+struct ErrorExtensions {
+	#[serde(default)]
+	code: Option<String>,
+}
+
+/// Parses a GraphQL response body, returning its data.
+///
+/// If the response has errors, they're returned as an [AidokuError::GraphqlError] with
+/// the server's messages, even if it also has partial data.
+pub fn parse_response<T: DeserializeOwned>(data: &[u8]) -> Result<T, AidokuError> {
+	let response: Response<T> = serde_json::from_slice(data)?;
+	if !response.errors.is_empty() {
+		return Err(AidokuError::GraphqlError(
+			response
+				.errors
+				.into_iter()
+				.map(|error| error.message)
+				.collect(),
+		));
+	}
+	response
+		.data
+		.ok_or(AidokuError::RequestError(RequestError::MissingData))
+}
@@ -3,8 +3,13 @@ extern crate alloc;
 
 #[cfg(feature = "imports")]
 pub mod cfemail;
+pub mod contract;
 #[cfg(feature = "imports")]
 pub mod element;
+#[cfg(feature = "encoding")]
+pub mod encoding;
+#[cfg(all(feature = "json", feature = "imports"))]
+pub mod graphql;
 
 pub mod string;
 pub mod uri;
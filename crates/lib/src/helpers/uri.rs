@@ -1,6 +1,7 @@
 //! Module for encoding and decoding URIs.
 extern crate alloc;
 
+#[cfg(feature = "imports")]
 use crate::AidokuError;
 use alloc::{
 	format,
@@ -514,6 +515,7 @@ impl SerializeError {
 	}
 }
 
+#[cfg(feature = "imports")]
 impl From<SerializeError> for AidokuError {
 	fn from(error: SerializeError) -> Self {
 		Self::Message(error.to_string())
@@ -3,7 +3,7 @@ use super::{html::HtmlError, js::JsError, net::RequestError};
 #[cfg(feature = "json")]
 use crate::alloc::rc::Rc;
 use crate::{
-	alloc::{String, string::ToString},
+	alloc::{String, Vec, string::ToString},
 	imports::canvas::CanvasError,
 };
 use core::{fmt::Display, str::Utf8Error};
@@ -32,6 +32,8 @@ pub enum AidokuError {
 	JsonParseError(Rc<serde_json::Error>),
 	/// Deserialization error.
 	DeserializeError,
+	/// A GraphQL server returned errors, with their messages.
+	GraphqlError(Vec<String>),
 }
 
 impl AidokuError {
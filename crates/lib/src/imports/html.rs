@@ -88,6 +88,12 @@ pub enum HtmlError {
 	InvalidQuery,
 	NoResult,
 	SwiftSoupError,
+	/// No element matched the selector of an extracted field.
+	MissingElement(String),
+	/// The selected element didn't have the value of an extracted field, e.g. an attribute.
+	MissingValue(String),
+	/// The value of an extracted field couldn't be parsed.
+	InvalidValue(String),
 }
 
 impl HtmlError {
@@ -406,6 +412,14 @@ impl Drop for Node {
 	}
 }
 
+/// A type that can be extracted from an HTML element.
+///
+/// This is usually implemented with `#[derive(FromElement)]` from the `aidoku-derive` crate.
+pub trait FromElement: Sized {
+	/// Extract a value from the element.
+	fn from_element(element: &Element) -> Result<Self, HtmlError>;
+}
+
 /// A single HTML element.
 pub struct Element(pub(crate) Node);
 
@@ -440,10 +454,34 @@ impl Element {
 		Some(unsafe { Element::from(rid) })
 	}
 
+	/// Extract a value from this element.
+	///
+	/// # Examples
+	/// ```ignore
+	/// use aidoku::imports::html::{FromElement, Html};
+	/// use aidoku_derive::FromElement;
+	///
+	/// #[derive(FromElement)]
+	/// struct Item {
+	///     #[html(select = "a", attr = "href")]
+	///     key: String,
+	///     #[html(select = "a")]
+	///     title: String,
+	/// }
+	///
+	/// let html = Html::parse("<div><a href=\"/manga/1\">Title</a></div>").unwrap();
+	/// let item: Item = html.select_first("div").unwrap().extract()?;
+	/// ```
+	pub fn extract<T: FromElement>(&self) -> Result<T, HtmlError> {
+		T::from_element(self)
+	}
+
 	/// Get an attribute value by its key.
 	///
 	/// To get an absolute URL from an attribute that may be a relative URL,
-	/// prefix the key with `abs:`.
+	/// prefix the key with `abs:`. Relative URLs are resolved against the
+	/// document's `<base href>` if present, or otherwise the URL that the
+	/// document was loaded from, and `None` is returned if they can't be resolved.
 	///
 	/// # Examples
 	/// ```ignore
@@ -860,6 +898,13 @@ impl Document {
 	pub fn select_first<T: AsRef<str>>(&self, css_query: T) -> Option<Element> {
 		self.0.select_first(css_query)
 	}
+
+	/// Extract a value from the document's root element.
+	///
+	/// See [Element::extract].
+	pub fn extract<T: FromElement>(&self) -> Result<T, HtmlError> {
+		self.0.extract()
+	}
 }
 
 impl From<Document> for Element {
@@ -976,6 +1021,13 @@ impl ElementList {
 		read_string_and_destroy(rid)
 	}
 
+	/// Extract a value from each element.
+	///
+	/// See [Element::extract].
+	pub fn extract_all<T: FromElement>(self) -> Result<Vec<T>, HtmlError> {
+		self.map(|element| T::from_element(&element)).collect()
+	}
+
 	/// Remove each element from the DOM.
 	pub fn remove(self) {
 		_ = unsafe { remove(self.rid) };
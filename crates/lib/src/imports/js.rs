@@ -1,4 +1,5 @@
 //! Module for running JavaScript and managing web views.
+pub use super::net::Cookie;
 use super::{
 	FFIResult, Rid,
 	net::Request,
@@ -339,14 +340,3 @@ impl WebViewUserScript {
 		}
 	}
 }
-
-#[derive(Debug, serde::Deserialize)]
-pub struct Cookie {
-	pub name: String,
-	pub value: String,
-	pub expires_date: Option<i64>,
-	pub domain: String,
-	pub path: String,
-	pub is_secure: bool,
-	pub is_http_only: bool,
-}
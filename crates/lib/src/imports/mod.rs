@@ -6,6 +6,8 @@ pub mod error;
 pub mod html;
 pub mod js;
 pub mod net;
+#[cfg(feature = "test")]
+pub mod snapshot;
 pub mod std;
 
 /// A standard descriptor, used for data exchange between the runner and the source (reference id).
@@ -3,10 +3,26 @@ use super::{
 	FFIResult, Rid,
 	canvas::ImageRef,
 	error::AidokuError,
-	html::Document,
-	std::{destroy, read_string_and_destroy},
+	html::{Document, Html},
+	std::{current_date, destroy, read, read_string_and_destroy, sleep},
 };
-use crate::alloc::{String, Vec};
+use crate::alloc::{String, Vec, format, vec};
+#[cfg(feature = "encoding")]
+use crate::helpers::encoding::{self, Encoding};
+#[cfg(feature = "helpers")]
+use crate::{
+	alloc::string::ToString,
+	helpers::uri::{QueryParameters, SerializeError},
+};
+use cache::{CacheEntry, CacheUpdate};
+use core::{
+	cell::{RefCell, UnsafeCell},
+	ops::{Bound, RangeBounds},
+};
+
+mod cache;
+
+pub use cache::clear_cache;
 
 /// An HTTP request method.
 #[repr(C)]
@@ -23,11 +39,44 @@ pub enum HttpMethod {
 	Trace,
 }
 
+/// How a request handles redirect responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedirectPolicy {
+	/// Follow redirects, up to the host's default limit.
+	#[default]
+	Follow,
+	/// Don't follow redirects, and return the redirect response instead.
+	None,
+	/// Follow at most the given number of redirects, and return the last redirect
+	/// response when the limit is reached.
+	Limit(u32),
+}
+
+impl RedirectPolicy {
+	/// The value passed to the host, where a negative value uses the default limit.
+	fn ffi_value(self) -> i32 {
+		match self {
+			Self::Follow => -1,
+			Self::None => 0,
+			Self::Limit(max) => max.min(i32::MAX as u32) as i32,
+		}
+	}
+}
+
 #[link(wasm_import_module = "net")]
 unsafe extern "C" {
 	fn init(method: HttpMethod) -> Rid;
+	/// Sends the request, replacing any previous response.
+	///
+	/// A request can be sent again with the same rid when it's retried, so the host has to
+	/// keep its url, headers, body and timeout after sending.
 	fn send(rid: Rid) -> FFIResult;
+	/// Sends the requests in parallel, with the same re-send contract as `send`.
 	fn send_all(rd: *mut Rid, len: usize) -> FFIResult;
+	fn start(rid: Rid) -> FFIResult;
+	fn poll(rid: Rid) -> FFIResult;
+	fn wait(rid: Rid) -> FFIResult;
+	fn wait_any(rd: *const Rid, len: usize) -> FFIResult;
 
 	fn set_url(rid: Rid, value: *const u8, len: usize) -> FFIResult;
 	fn set_header(
@@ -39,17 +88,43 @@ unsafe extern "C" {
 	) -> FFIResult;
 	fn set_body(rid: Rid, value: *const u8, len: usize) -> FFIResult;
 	fn set_timeout(rid: Rid, value: f64) -> FFIResult;
+	fn set_redirect_policy(rid: Rid, max_redirects: i32) -> FFIResult;
 
 	fn data_len(rid: Rid) -> FFIResult;
 	fn read_data(rid: Rid, buffer: *mut u8, size: usize) -> FFIResult;
+	fn read_data_at(rid: Rid, offset: usize, buffer: *mut u8, size: usize) -> FFIResult;
 	fn get_image(rid: Rid) -> FFIResult;
 	fn get_header(rid: Rid, key: *const u8, key_len: usize) -> FFIResult;
+	fn get_headers(rid: Rid) -> FFIResult;
 	fn get_status_code(rid: Rid) -> FFIResult;
 	fn get_url(rid: Rid) -> FFIResult;
+	fn get_redirects(rid: Rid) -> FFIResult;
 	fn html(rid: Rid) -> FFIResult;
 
+	#[link_name = "get_cookies"]
+	fn net_get_cookies(domain: *const u8, domain_len: usize) -> FFIResult;
+	#[link_name = "set_cookie"]
+	fn net_set_cookie(cookie: *const u8, cookie_len: usize) -> FFIResult;
+	#[link_name = "delete_cookie"]
+	fn net_delete_cookie(
+		name: *const u8,
+		name_len: usize,
+		domain: *const u8,
+		domain_len: usize,
+	) -> FFIResult;
+	#[link_name = "delete_cookies"]
+	fn net_delete_cookies(domain: *const u8, domain_len: usize) -> FFIResult;
+
 	#[link_name = "set_rate_limit"]
 	fn net_set_rate_limit(permits: i32, period: i32, unit: i32);
+	#[link_name = "set_host_rate_limit"]
+	fn net_set_host_rate_limit(
+		pattern: *const u8,
+		pattern_len: usize,
+		permits: i32,
+		period: i32,
+		unit: i32,
+	) -> FFIResult;
 }
 
 /// A time unit for rate limiting.
@@ -85,6 +160,13 @@ pub enum RequestError {
 	FailedMemoryWrite,
 	NotAnImage,
 	Closed,
+	/// The response is a bot protection challenge, like Cloudflare's, which needs
+	/// to be solved in a web view.
+	Challenge,
+	/// The requested byte range is empty.
+	InvalidRange,
+	/// The rate limit has a negative period or an unknown time unit.
+	InvalidRateLimit,
 }
 
 impl RequestError {
@@ -102,11 +184,706 @@ impl RequestError {
 			-10 => Some(Self::RequestError),
 			-11 => Some(Self::FailedMemoryWrite),
 			-12 => Some(Self::NotAnImage),
+			-13 => Some(Self::InvalidRateLimit),
 			_ => None,
 		}
 	}
 }
 
+/// A policy for automatically retrying failed requests.
+///
+/// Requests are retried when they fail to send, or when the response has one of the
+/// retryable status codes (429 and 503 by default). Between attempts, the current thread
+/// sleeps for the delay given by the response's `Retry-After` header, or otherwise an
+/// exponentially increasing delay. If the server asks to wait longer than the maximum delay,
+/// the request isn't retried.
+///
+/// # Examples
+///
+/// ```ignore
+/// use aidoku::imports::net::{Request, RetryPolicy};
+/// let response = Request::get("https://example.com")?
+///     .retry(RetryPolicy::new(5).delay(2))
+///     .send()?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+	max_attempts: u32,
+	delay: u32,
+	backoff: u32,
+	max_delay: u32,
+	status_codes: Vec<i32>,
+	retry_errors: bool,
+}
+
+impl RetryPolicy {
+	/// Create a new retry policy with the maximum number of attempts, including the first one.
+	pub fn new(max_attempts: u32) -> Self {
+		Self {
+			max_attempts,
+			delay: 1,
+			backoff: 2,
+			max_delay: 30,
+			status_codes: [429, 503].into(),
+			retry_errors: true,
+		}
+	}
+
+	/// Set the delay in seconds before the first retry.
+	pub fn delay(mut self, seconds: u32) -> Self {
+		self.delay = seconds;
+		self
+	}
+
+	/// Set the factor that the delay is multiplied by after each retry.
+	pub fn backoff(mut self, factor: u32) -> Self {
+		self.backoff = factor;
+		self
+	}
+
+	/// Set the maximum delay in seconds between attempts.
+	///
+	/// Backoff delays are capped at this value, and responses asking for a longer delay with
+	/// `Retry-After` aren't retried.
+	pub fn max_delay(mut self, seconds: u32) -> Self {
+		self.max_delay = seconds;
+		self
+	}
+
+	/// Set the response status codes that should be retried.
+	pub fn status_codes<I: IntoIterator<Item = i32>>(mut self, status_codes: I) -> Self {
+		self.status_codes = status_codes.into_iter().collect();
+		self
+	}
+
+	/// Set whether requests that fail without a response should be retried.
+	pub fn retry_errors(mut self, retry_errors: bool) -> Self {
+		self.retry_errors = retry_errors;
+		self
+	}
+
+	/// Returns true if another attempt should be made after the given attempt (starting at 1)
+	/// finished with the given status code or error.
+	pub fn should_retry(&self, attempt: u32, result: Result<i32, RequestError>) -> bool {
+		if attempt >= self.max_attempts {
+			return false;
+		}
+		match result {
+			Ok(status_code) => self.status_codes.contains(&status_code),
+			Err(RequestError::RequestError) => self.retry_errors,
+			Err(_) => false,
+		}
+	}
+
+	/// Returns the delay in seconds to wait after the given attempt (starting at 1).
+	///
+	/// If the server requested a delay with `Retry-After`, it's used instead of the backoff,
+	/// and `None` is returned if it's longer than the maximum delay.
+	pub fn retry_delay(&self, attempt: u32, retry_after: Option<u32>) -> Option<u32> {
+		match retry_after {
+			Some(delay) => (delay <= self.max_delay).then_some(delay),
+			None => Some(
+				self.delay
+					.saturating_mul(self.backoff.saturating_pow(attempt.saturating_sub(1)))
+					.min(self.max_delay),
+			),
+		}
+	}
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self::new(3)
+	}
+}
+
+/// Status codes that bot protection challenges are sent with.
+const CHALLENGE_STATUS_CODES: [i32; 3] = [403, 429, 503];
+
+/// Markers in the body of Cloudflare and DDoS-Guard challenge pages.
+const CHALLENGE_MARKERS: [&str; 6] = [
+	"/cdn-cgi/challenge-platform/",
+	"cf_chl_opt",
+	"cf-browser-verification",
+	"<title>Just a moment...</title>",
+	"<title>DDoS-Guard</title>",
+	"check.ddos-guard.net",
+];
+
+/// Returns true if a response is a bot protection challenge, like Cloudflare's
+/// "Just a moment..." page, based on its status code, `Server` and `cf-mitigated`
+/// headers, and body.
+pub fn is_challenge(
+	status_code: i32,
+	server: Option<&str>,
+	cf_mitigated: Option<&str>,
+	body: &[u8],
+) -> bool {
+	match challenge_from_headers(status_code, server, cf_mitigated) {
+		Some(challenge) => challenge,
+		None => has_challenge_marker(body),
+	}
+}
+
+/// Decides whether a response is a challenge from its status code and headers, or
+/// returns `None` if its body needs to be checked.
+///
+/// Only responses from Cloudflare and DDoS-Guard servers need their body checked.
+fn challenge_from_headers(
+	status_code: i32,
+	server: Option<&str>,
+	cf_mitigated: Option<&str>,
+) -> Option<bool> {
+	if !CHALLENGE_STATUS_CODES.contains(&status_code) {
+		return Some(false);
+	}
+	if cf_mitigated.is_some_and(|value| value.trim().eq_ignore_ascii_case("challenge")) {
+		return Some(true);
+	}
+	let protected = server.is_some_and(|server| {
+		let server = server.to_ascii_lowercase();
+		server.contains("cloudflare") || server.contains("ddos-guard")
+	});
+	if protected { None } else { Some(false) }
+}
+
+fn has_challenge_marker(body: &[u8]) -> bool {
+	CHALLENGE_MARKERS.iter().any(|marker| {
+		body.windows(marker.len())
+			.any(|window| window.eq_ignore_ascii_case(marker.as_bytes()))
+	})
+}
+
+/// The headers of a response.
+///
+/// Header names are case-insensitive, and a header can have multiple values,
+/// e.g. `Set-Cookie`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Headers {
+	entries: Vec<(String, String)>,
+}
+
+impl Headers {
+	/// Create headers from name and value pairs.
+	pub fn new(entries: Vec<(String, String)>) -> Self {
+		Self { entries }
+	}
+
+	/// Get the first value of a header.
+	pub fn get<T: AsRef<str>>(&self, name: T) -> Option<&str> {
+		self.get_all(name).next()
+	}
+
+	/// Get all values of a header, in the order they were received.
+	pub fn get_all<T: AsRef<str>>(&self, name: T) -> impl Iterator<Item = &str> {
+		self.entries.iter().filter_map(move |(key, value)| {
+			key.eq_ignore_ascii_case(name.as_ref())
+				.then_some(value.as_str())
+		})
+	}
+
+	/// Returns true if the header is present.
+	pub fn contains<T: AsRef<str>>(&self, name: T) -> bool {
+		self.get(name).is_some()
+	}
+
+	/// Iterate over all header names and values.
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+		self.entries
+			.iter()
+			.map(|(key, value)| (key.as_str(), value.as_str()))
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// Parse the cookies set by the `Set-Cookie` headers.
+	///
+	/// Cookies without a domain attribute use the given domain, which should be the
+	/// host of the response url, and `Max-Age` is relative to the given current date.
+	pub fn cookies(&self, domain: &str, now: i64) -> Vec<Cookie> {
+		self.get_all("Set-Cookie")
+			.filter_map(|value| Cookie::parse(value, domain, now))
+			.collect()
+	}
+}
+
+/// An HTTP cookie.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Cookie {
+	pub name: String,
+	pub value: String,
+	/// The expiration date as a Unix timestamp, or none for session cookies.
+	pub expires_date: Option<i64>,
+	pub domain: String,
+	pub path: String,
+	pub is_secure: bool,
+	pub is_http_only: bool,
+}
+
+impl Cookie {
+	/// Create a session cookie for a domain, with the path `/`.
+	pub fn new<T: Into<String>, U: Into<String>, V: Into<String>>(
+		name: T,
+		value: U,
+		domain: V,
+	) -> Self {
+		Self {
+			name: name.into(),
+			value: value.into(),
+			expires_date: None,
+			domain: domain.into(),
+			path: "/".into(),
+			is_secure: false,
+			is_http_only: false,
+		}
+	}
+
+	/// Parse the value of a `Set-Cookie` header.
+	///
+	/// The domain is used when the cookie doesn't have a domain attribute, and `Max-Age`
+	/// is relative to the given current date.
+	pub fn parse(set_cookie: &str, domain: &str, now: i64) -> Option<Self> {
+		let mut attributes = set_cookie.split(';');
+		let (name, value) = attributes.next()?.split_once('=')?;
+		let name = name.trim();
+		if name.is_empty() {
+			return None;
+		}
+		let mut cookie = Self {
+			name: name.into(),
+			value: value.trim().trim_matches('"').into(),
+			expires_date: None,
+			domain: domain.into(),
+			path: "/".into(),
+			is_secure: false,
+			is_http_only: false,
+		};
+		let mut max_age = None;
+		for attribute in attributes {
+			let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+			let value = value.trim();
+			match key.trim().to_ascii_lowercase().as_str() {
+				"expires" => {
+					if let Some(date) = parse_http_date(value) {
+						cookie.expires_date = Some(date);
+					}
+				}
+				"max-age" => max_age = value.parse::<i64>().ok(),
+				"domain" if !value.is_empty() => {
+					let domain = value.trim_start_matches('.').to_ascii_lowercase();
+					// cookies can only be set for the response's host and its parent domains
+					let host = cookie.domain.to_ascii_lowercase();
+					let matches = host == domain
+						|| host
+							.strip_suffix(domain.as_str())
+							.is_some_and(|prefix| prefix.ends_with('.'));
+					if !matches {
+						return None;
+					}
+					cookie.domain = domain;
+				}
+				"path" if value.starts_with('/') => cookie.path = value.into(),
+				"secure" => cookie.is_secure = true,
+				"httponly" => cookie.is_http_only = true,
+				_ => {}
+			}
+		}
+		// max-age takes precedence over expires
+		if let Some(max_age) = max_age {
+			cookie.expires_date = Some(now.saturating_add(max_age.max(0)));
+		}
+		Some(cookie)
+	}
+}
+
+/// Parses a date in the formats used by `Expires` and `Retry-After` headers, e.g.
+/// `Wed, 21 Oct 2015 07:28:00 GMT` or `Wed, 21-Oct-15 07:28:00 GMT`, as a Unix timestamp.
+fn parse_http_date(value: &str) -> Option<i64> {
+	const MONTHS: [&str; 12] = [
+		"jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+	];
+	let (mut day, mut month, mut year, mut time) = (None, None, None, None);
+	for token in value.split([' ', '-', ',']).filter(|t| !t.is_empty()) {
+		if time.is_none() && token.contains(':') {
+			let mut parts = token.split(':').map(|part| part.parse::<i64>().ok());
+			let (hour, minute, second) = (parts.next()??, parts.next()??, parts.next()??);
+			time = Some(hour * 3600 + minute * 60 + second);
+		} else if let Ok(number) = token.parse::<i64>() {
+			if day.is_none() && token.len() <= 2 {
+				day = Some(number);
+			} else if year.is_none() {
+				year = Some(match (token.len(), number) {
+					(2, 0..=69) => number + 2000,
+					(2, _) => number + 1900,
+					_ => number,
+				});
+			}
+		} else if month.is_none() {
+			let token = token.to_ascii_lowercase();
+			month = MONTHS
+				.iter()
+				.position(|month| token.starts_with(month))
+				.map(|idx| idx as i64 + 1);
+		}
+	}
+	let (day, month, year) = (day?, month?, year?);
+	if !(1..=31).contains(&day) {
+		return None;
+	}
+	// days since the unix epoch, from howard hinnant's days_from_civil
+	let y = if month <= 2 { year - 1 } else { year };
+	let era = y.div_euclid(400);
+	let yoe = y - era * 400;
+	let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+	let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+	let days = era * 146097 + doe - 719468;
+	Some(days * 86400 + time?)
+}
+
+/// Returns the host of a url, e.g. `example.com` for `https://user@example.com:8080/path`.
+fn url_host(url: &str) -> Option<&str> {
+	let (_, rest) = url.split_once("://")?;
+	let authority = rest.split(['/', '?', '#']).next()?;
+	let host = authority.rsplit('@').next()?;
+	let host = match host.strip_prefix('[') {
+		Some(host) => host.split(']').next()?,
+		None => host.split(':').next()?,
+	};
+	(!host.is_empty()).then_some(host)
+}
+
+/// A `multipart/form-data` request body.
+///
+/// # Examples
+///
+/// ```ignore
+/// use aidoku::imports::net::{MultipartForm, Request};
+/// let form = MultipartForm::new()
+///     .text("title", "Chapter 1")
+///     .file("image", "page.png", "image/png", image_data);
+/// let response = Request::post("https://example.com/upload")?
+///     .multipart(form)
+///     .send()?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultipartForm {
+	parts: Vec<MultipartPart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MultipartPart {
+	name: String,
+	filename: Option<String>,
+	content_type: Option<String>,
+	data: Vec<u8>,
+}
+
+impl MultipartForm {
+	/// Create an empty form.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a text field to the form.
+	pub fn text<T: AsRef<str>, U: AsRef<str>>(mut self, name: T, value: U) -> Self {
+		self.parts.push(MultipartPart {
+			name: name.as_ref().into(),
+			filename: None,
+			content_type: None,
+			data: value.as_ref().as_bytes().into(),
+		});
+		self
+	}
+
+	/// Add a file field to the form, with a file name and content type.
+	pub fn file<T: AsRef<str>, U: AsRef<str>, V: AsRef<str>, W: Into<Vec<u8>>>(
+		mut self,
+		name: T,
+		filename: U,
+		content_type: V,
+		data: W,
+	) -> Self {
+		self.parts.push(MultipartPart {
+			name: name.as_ref().into(),
+			filename: Some(filename.as_ref().into()),
+			content_type: Some(content_type.as_ref().into()),
+			data: data.into(),
+		});
+		self
+	}
+
+	/// Returns a boundary that doesn't occur in any of the parts.
+	fn boundary(&self) -> String {
+		let mut counter = 0usize;
+		loop {
+			let boundary = format!("AidokuFormBoundary{counter:016x}");
+			let occurs = self.parts.iter().any(|part| {
+				part.data
+					.windows(boundary.len())
+					.any(|window| window == boundary.as_bytes())
+			});
+			if !occurs {
+				return boundary;
+			}
+			counter += 1;
+		}
+	}
+
+	/// Builds the form, returning the content type and the body data.
+	pub fn build(&self) -> (String, Vec<u8>) {
+		// quotes and newlines in names are percent-encoded, like browsers do
+		fn escape(value: &str) -> String {
+			value
+				.replace('"', "%22")
+				.replace('\r', "%0D")
+				.replace('\n', "%0A")
+		}
+
+		let boundary = self.boundary();
+		let mut data = Vec::new();
+		for part in &self.parts {
+			data.extend_from_slice(b"--");
+			data.extend_from_slice(boundary.as_bytes());
+			data.extend_from_slice(b"\r\nContent-Disposition: form-data; name=\"");
+			data.extend_from_slice(escape(&part.name).as_bytes());
+			data.push(b'"');
+			if let Some(filename) = &part.filename {
+				data.extend_from_slice(b"; filename=\"");
+				data.extend_from_slice(escape(filename).as_bytes());
+				data.push(b'"');
+			}
+			if let Some(content_type) = &part.content_type {
+				data.extend_from_slice(b"\r\nContent-Type: ");
+				data.extend_from_slice(content_type.as_bytes());
+			}
+			data.extend_from_slice(b"\r\n\r\n");
+			data.extend_from_slice(&part.data);
+			data.extend_from_slice(b"\r\n");
+		}
+		data.extend_from_slice(b"--");
+		data.extend_from_slice(boundary.as_bytes());
+		data.extend_from_slice(b"--\r\n");
+		(format!("multipart/form-data; boundary={boundary}"), data)
+	}
+}
+
+/// Headers and base URL that are applied to every new request.
+struct RequestDefaults {
+	headers: Vec<(String, String)>,
+	base_url: Option<String>,
+	/// The maximum size of the HTTP cache in bytes, or zero if it's disabled.
+	cache_size: usize,
+}
+
+struct DefaultsCell(UnsafeCell<RequestDefaults>);
+
+// sources are single threaded
+unsafe impl Sync for DefaultsCell {}
+
+static DEFAULTS: DefaultsCell = DefaultsCell(UnsafeCell::new(RequestDefaults {
+	headers: Vec::new(),
+	base_url: None,
+	cache_size: 0,
+}));
+
+/// Calls the closure with the request defaults.
+///
+/// The closure must not call `with_defaults` itself.
+fn with_defaults<R>(f: impl FnOnce(&mut RequestDefaults) -> R) -> R {
+	// sources are single threaded and the borrow doesn't outlive the closure
+	f(unsafe { &mut *DEFAULTS.0.get() })
+}
+
+/// Set a header that's added to every request created afterwards.
+///
+/// Headers set on a request itself take precedence over default headers.
+///
+/// # Examples
+///
+/// ```ignore
+/// use aidoku::imports::net::{Request, set_default_header};
+/// set_default_header("Referer", "https://example.com/");
+/// // sent with the referer header
+/// Request::get("https://example.com/manga/1")?.send()?;
+/// ```
+pub fn set_default_header<T: AsRef<str>, U: AsRef<str>>(key: T, value: U) {
+	let key = key.as_ref();
+	let value = String::from(value.as_ref());
+	with_defaults(|defaults| {
+		match defaults
+			.headers
+			.iter_mut()
+			.find(|(k, _)| k.eq_ignore_ascii_case(key))
+		{
+			Some(header) => header.1 = value,
+			None => defaults.headers.push((key.into(), value)),
+		}
+	});
+}
+
+/// Remove a header that was set with [set_default_header].
+pub fn remove_default_header<T: AsRef<str>>(key: T) {
+	let key = key.as_ref();
+	with_defaults(|defaults| {
+		defaults
+			.headers
+			.retain(|(k, _)| !k.eq_ignore_ascii_case(key))
+	});
+}
+
+/// Set a base URL that relative request URLs are resolved against.
+///
+/// # Examples
+///
+/// ```ignore
+/// use aidoku::imports::net::{Request, set_base_url};
+/// set_base_url("https://example.com/api/");
+/// // requests https://example.com/api/search?q=test
+/// Request::get("search?q=test")?.send()?;
+/// ```
+pub fn set_base_url<T: AsRef<str>>(url: T) {
+	with_defaults(|defaults| defaults.base_url = Some(url.as_ref().into()));
+}
+
+/// Remove the base URL that was set with [set_base_url].
+pub fn clear_base_url() {
+	with_defaults(|defaults| defaults.base_url = None);
+}
+
+/// Enable the HTTP cache for GET requests, with a maximum total size of the cached
+/// responses in bytes.
+///
+/// Responses with an `ETag` or `Last-Modified` header are stored in the defaults store
+/// when their whole body is read with [Response::get_data], [Response::get_string],
+/// [Response::get_html] or the JSON getters, and later requests to the same URL are
+/// sent as conditional requests. If the server responds with `304 Not Modified`, the
+/// response is transparently replaced with the cached one. When the cache is full, the
+/// least recently used responses are removed.
+///
+/// Requests can opt out with [Request::cache].
+///
+/// # Examples
+///
+/// ```ignore
+/// use aidoku::imports::net::{Request, enable_cache};
+/// enable_cache(4 * 1024 * 1024);
+/// // if the chapter list hasn't changed, the cached body is used
+/// let html = Request::get("https://example.com/manga/1")?.html()?;
+/// ```
+pub fn enable_cache(max_size: usize) {
+	with_defaults(|defaults| defaults.cache_size = max_size);
+}
+
+/// Disable the HTTP cache. Cached responses are kept until [clear_cache] is called.
+pub fn disable_cache() {
+	with_defaults(|defaults| defaults.cache_size = 0);
+}
+
+/// Resolves a URL against a base URL.
+///
+/// Absolute URLs are returned as is, and relative URLs are resolved like links in a
+/// web page, e.g. `/search` against `https://example.com/api/` is `https://example.com/search`.
+/// `.` and `..` segments are removed from the resolved path.
+pub fn resolve_url(base: &str, url: &str) -> String {
+	let has_scheme = url.split_once(':').is_some_and(|(scheme, _)| {
+		scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+			&& scheme
+				.chars()
+				.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+	});
+	if has_scheme {
+		return url.into();
+	}
+	let (scheme, rest) = base.split_once("://").unwrap_or(("", base));
+	if let Some(url) = url.strip_prefix("//") {
+		let host_len = url.find(['/', '?', '#']).unwrap_or(url.len());
+		let (host, path) = url.split_at(host_len);
+		return [scheme, "://", host, &remove_dot_segments(path)].concat();
+	}
+	// split the base into the origin, path, and query
+	let rest = rest.split('#').next().unwrap_or_default();
+	let origin_len = rest.find(['/', '?']).unwrap_or(rest.len());
+	let (host, path_and_query) = rest.split_at(origin_len);
+	let origin = [scheme, "://", host].concat();
+	let path = path_and_query.split('?').next().unwrap_or_default();
+
+	if url.is_empty() {
+		[origin.as_str(), path_and_query].concat()
+	} else if url.starts_with('/') {
+		[origin.as_str(), &remove_dot_segments(url)].concat()
+	} else if url.starts_with('?') {
+		[origin.as_str(), path, url].concat()
+	} else if url.starts_with('#') {
+		[origin.as_str(), path_and_query, url].concat()
+	} else {
+		// replace the last segment of the base path
+		let dir = match path.rfind('/') {
+			Some(idx) => &path[..=idx],
+			None => "/",
+		};
+		[origin.as_str(), &remove_dot_segments(&[dir, url].concat())].concat()
+	}
+}
+
+/// Removes the `.` and `..` segments from the path of a URL path, query and fragment,
+/// following RFC 3986.
+fn remove_dot_segments(url: &str) -> String {
+	let path_len = url.find(['?', '#']).unwrap_or(url.len());
+	let (path, rest) = url.split_at(path_len);
+	if !path.starts_with('/') {
+		return url.into();
+	}
+	let segments = path[1..].split('/').collect::<Vec<_>>();
+	let mut output = Vec::new();
+	for (idx, segment) in segments.iter().enumerate() {
+		let is_last = idx == segments.len() - 1;
+		match *segment {
+			"." | ".." => {
+				if *segment == ".." {
+					output.pop();
+				}
+				// a trailing dot segment leaves the path ending in a slash
+				if is_last {
+					output.push("");
+				}
+			}
+			segment => output.push(segment),
+		}
+	}
+	["/", &output.join("/"), rest].concat()
+}
+
+/// Reads the `Retry-After` header of a response as a number of seconds.
+fn retry_after(rid: Rid) -> Option<u32> {
+	let key = "Retry-After";
+	let value = read_string_and_destroy(unsafe { get_header(rid, key.as_ptr(), key.len()) })?;
+	let value = value.trim();
+	if let Ok(seconds) = value.parse() {
+		return Some(seconds);
+	}
+	// the value can also be an http date
+	let date = parse_http_date(value)?;
+	Some((date - current_date()).clamp(0, u32::MAX as i64) as u32)
+}
+
+/// Sends the given requests in parallel, returning the error for each request.
+fn send_ids(ids: &mut [Rid]) -> Vec<Option<RequestError>> {
+	let result = unsafe { send_all(ids.as_mut_ptr(), ids.len()) };
+	if result == 0 {
+		ids.iter().map(|_| None).collect()
+	} else {
+		// one or more of the requests failed
+		// the error codes are stored in the ids slice
+		ids.iter().map(|id| RequestError::from(*id)).collect()
+	}
+}
+
 /// Macro for generating convenience HTTP methods, e.g.
 /// Request::get, Request::post.
 #[doc(hidden)]
@@ -130,6 +907,10 @@ pub struct Request {
 	pub rid: Rid,
 	http_method: HttpMethod,
 	url: Option<String>,
+	retry: Option<RetryPolicy>,
+	cache: bool,
+	/// Whether the request has a `Range` header, which skips the cache.
+	ranged: bool,
 	/// Whether the request should be closed after being dropped.
 	///
 	/// This property is exposed for the functions that the [register_source](crate::register_source)
@@ -137,6 +918,147 @@ pub struct Request {
 	pub should_close: bool,
 }
 
+/// The range of the body contained in a partial response, from the `Content-Range` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+	/// The offset of the first byte.
+	pub start: u64,
+	/// The offset of the last byte, inclusive.
+	pub end: u64,
+	/// The length of the full body, if known.
+	pub total: Option<u64>,
+}
+
+impl ContentRange {
+	/// Parse a `Content-Range` header value, e.g. `bytes 0-99/1234`.
+	pub fn parse(value: &str) -> Option<Self> {
+		let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+		let (start, end) = range.trim().split_once('-')?;
+		let total = match total.trim() {
+			"*" => None,
+			total => Some(total.parse().ok()?),
+		};
+		Some(Self {
+			start: start.trim().parse().ok()?,
+			end: end.trim().parse().ok()?,
+			total,
+		})
+	}
+
+	/// The number of bytes in the range.
+	pub fn len(&self) -> u64 {
+		self.end.saturating_sub(self.start) + 1
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.end < self.start
+	}
+}
+
+/// Returns the `Range` header value for a range of bytes, or `None` if it's empty.
+fn range_header<R: RangeBounds<u64>>(range: R) -> Option<String> {
+	let start = match range.start_bound() {
+		Bound::Included(start) => *start,
+		Bound::Excluded(start) => start.checked_add(1)?,
+		Bound::Unbounded => 0,
+	};
+	let end = match range.end_bound() {
+		Bound::Included(end) => Some(*end),
+		Bound::Excluded(end) => Some(end.checked_sub(1)?),
+		Bound::Unbounded => None,
+	};
+	match end {
+		Some(end) if end < start => None,
+		Some(end) => Some(format!("bytes={start}-{end}")),
+		None => Some(format!("bytes={start}-")),
+	}
+}
+
+/// An iterator over the body of a response in chunks, created with [Response::chunks].
+pub struct DataChunks<'a> {
+	response: &'a Response,
+	offset: usize,
+	chunk_size: usize,
+	finished: bool,
+}
+
+impl Iterator for DataChunks<'_> {
+	type Item = Result<Vec<u8>, RequestError>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.finished {
+			return None;
+		}
+		let mut buffer = vec![0; self.chunk_size];
+		match self.response.read_data_at(self.offset, &mut buffer) {
+			Ok(0) => {
+				self.finished = true;
+				None
+			}
+			Ok(len) => {
+				buffer.truncate(len);
+				self.offset += len;
+				Some(Ok(buffer))
+			}
+			Err(error) => {
+				self.finished = true;
+				Some(Err(error))
+			}
+		}
+	}
+}
+
+/// A request that's being sent in the background, created with [Request::start].
+#[derive(Debug)]
+pub struct PendingRequest {
+	request: Request,
+	cache: Option<(String, Option<CacheEntry>)>,
+}
+
+impl PendingRequest {
+	/// Returns true if the request has finished, without blocking.
+	pub fn is_finished(&self) -> bool {
+		unsafe { poll(self.request.rid) == 1 }
+	}
+
+	/// Wait for the request to finish.
+	///
+	/// If the request has a [RetryPolicy], failed attempts are retried before returning.
+	pub fn wait(self) -> Result<Response, RequestError> {
+		let error = RequestError::from(unsafe { wait(self.request.rid) });
+		self.request.finish(error, self.cache)
+	}
+
+	/// Wait for any of the requests to finish, returning its index.
+	///
+	/// Returns an [InvalidDescriptor](RequestError::InvalidDescriptor) error if there
+	/// are no requests.
+	pub fn wait_any(requests: &[PendingRequest]) -> Result<usize, RequestError> {
+		if requests.is_empty() {
+			return Err(RequestError::InvalidDescriptor);
+		}
+		let ids: Vec<Rid> = requests.iter().map(|pending| pending.request.rid).collect();
+		let result = unsafe { wait_any(ids.as_ptr(), ids.len()) };
+		if let Some(error) = RequestError::from(result) {
+			return Err(error);
+		}
+		usize::try_from(result).map_err(|_| RequestError::InvalidDescriptor)
+	}
+
+	/// Wait for all of the requests to finish.
+	pub fn wait_all<I>(requests: I) -> Vec<Result<Response, RequestError>>
+	where
+		I: IntoIterator<Item = PendingRequest>,
+	{
+		requests.into_iter().map(|pending| pending.wait()).collect()
+	}
+
+	/// Get the URL of the request.
+	pub fn url(&self) -> Option<&String> {
+		self.request.url()
+	}
+}
+
 /// An HTTP response.
 #[derive(Debug)]
 pub struct Response {
@@ -145,6 +1067,13 @@ pub struct Response {
 	url: Option<String>,
 	/// The stored request response data.
 	pub data: Option<Vec<u8>>,
+	/// The cached response used in place of a `304 Not Modified` response.
+	cached: Option<CacheEntry>,
+	/// The cache key and validators of a response that's stored once its body is read.
+	uncached: RefCell<Option<(String, CacheEntry)>>,
+	/// The encoding used to decode the response, overriding the detected one.
+	#[cfg(feature = "encoding")]
+	encoding: Option<&'static Encoding>,
 }
 
 impl Request {
@@ -159,18 +1088,27 @@ impl Request {
 	/// Request::new("https://example.com", HttpMethod::Get).unwrap();
 	/// ```
 	pub fn new<T: AsRef<str>>(url: T, http_method: HttpMethod) -> Result<Self, RequestError> {
-		let url = url.as_ref();
-		unsafe {
-			let rid = init(http_method);
-			let mut request = Self {
-				rid,
-				http_method,
-				url: None,
-				should_close: true,
-			};
-			request.set_url(url)?;
-			Ok(request)
+		let mut request = Self::init(http_method);
+		request.set_url(url)?;
+		Ok(request)
+	}
+
+	/// Create a new request without a URL, with the default headers applied.
+	fn init(http_method: HttpMethod) -> Self {
+		let rid = unsafe { init(http_method) };
+		let mut request = Self {
+			rid,
+			http_method,
+			url: None,
+			retry: None,
+			cache: true,
+			ranged: false,
+			should_close: true,
+		};
+		for (key, value) in with_defaults(|defaults| defaults.headers.clone()) {
+			request.set_header(key, value);
 		}
+		request
 	}
 
 	convenience_http_methods! { get, HttpMethod::Get, "Create a new GET request with the given URL." }
@@ -181,43 +1119,55 @@ impl Request {
 	convenience_http_methods! { patch, HttpMethod::Patch, "Create a new PATCH request with the given URL." }
 
 	/// Send multiple requests in parallel, and wait for all of them to finish.
+	///
+	/// Requests with a [RetryPolicy] are retried together, without resending the
+	/// requests that succeeded.
 	pub fn send_all<I>(requests: I) -> Vec<Result<Response, RequestError>>
 	where
 		I: IntoIterator<Item = Request>,
 	{
-		let mut ids: Vec<i32> = Vec::new();
-		// mark all requests as sent and add their IDs to the vector
-		let responses = requests
-			.into_iter()
-			.map(|mut r| {
-				r.should_close = false;
-				ids.push(r.rid);
-				Ok(Response::from(r))
-			})
-			.collect();
+		let mut requests: Vec<Request> = requests.into_iter().collect();
+		let caches: Vec<_> = requests.iter_mut().map(|r| r.prepare_cache()).collect();
+		let mut ids: Vec<Rid> = requests.iter().map(|r| r.rid).collect();
+		let mut errors = send_ids(&mut ids);
+		let mut attempts = Vec::from_iter(requests.iter().map(|_| 1));
 
-		let result = unsafe { send_all(ids.as_mut_ptr(), ids.len()) };
-
-		if result == 0 {
-			// success, return result
-			responses
-		} else {
-			// one or more of the requests failed
-			// the error codes are stored in the ids vector
-			let mut idx = 0;
-			responses
-				.into_iter()
-				.map(|response| {
-					let error = ids.get(idx).and_then(|id| RequestError::from(*id));
-					let result = match error {
-						Some(e) => Err(e),
-						None => response,
-					};
-					idx += 1;
-					result
-				})
-				.collect()
+		loop {
+			// find the requests that should be retried, and wait for the longest delay
+			let mut retry = Vec::new();
+			let mut delay = 0;
+			for (idx, request) in requests.iter().enumerate() {
+				if let Some(request_delay) = request.retry_delay(attempts[idx], errors[idx]) {
+					retry.push(idx);
+					delay = delay.max(request_delay);
+				}
+			}
+			if retry.is_empty() {
+				break;
+			}
+			sleep(delay as i32);
+			let mut ids: Vec<Rid> = retry.iter().map(|idx| requests[*idx].rid).collect();
+			for (idx, error) in retry.into_iter().zip(send_ids(&mut ids)) {
+				errors[idx] = error;
+				attempts[idx] += 1;
+			}
 		}
+
+		requests
+			.into_iter()
+			.zip(errors)
+			.zip(caches)
+			.map(|((mut request, error), cache)| match error {
+				Some(error) => Err(error),
+				None => {
+					request.should_close = false;
+					let mut response = Response::from(request);
+					response.apply_cache(cache);
+					response.check_challenge()?;
+					Ok(response)
+				}
+			})
+			.collect()
 	}
 
 	/// Set an HTTP header in a builder.
@@ -230,17 +1180,129 @@ impl Request {
 	pub fn set_header<T: AsRef<str>>(&mut self, key: T, val: T) {
 		let key = key.as_ref();
 		let val = val.as_ref();
+		if key.eq_ignore_ascii_case("Range") {
+			self.ranged = true;
+		}
 		unsafe {
 			set_header(self.rid, key.as_ptr(), key.len(), val.as_ptr(), val.len());
 		};
 	}
 
+	/// Set the retry policy in a builder.
+	pub fn retry(mut self, policy: RetryPolicy) -> Self {
+		self.set_retry(policy);
+		self
+	}
+
+	/// Set the retry policy, which is used when the request is sent.
+	pub fn set_retry(&mut self, policy: RetryPolicy) {
+		self.retry = Some(policy);
+	}
+
+	/// Set whether the request uses the HTTP cache in a builder.
+	///
+	/// Requests use the cache by default once it's enabled with [enable_cache].
+	pub fn cache(mut self, enabled: bool) -> Self {
+		self.set_cache(enabled);
+		self
+	}
+
+	/// Set whether the request uses the HTTP cache.
+	pub fn set_cache(&mut self, enabled: bool) {
+		self.cache = enabled;
+	}
+
+	/// Adds the validators of the cached response to the request, returning the
+	/// cache key and cached response if the request uses the cache.
+	fn prepare_cache(&mut self) -> Option<(String, Option<CacheEntry>)> {
+		let max_size = with_defaults(|defaults| defaults.cache_size);
+		if !cache::uses_cache(self.cache, self.http_method, self.ranged, max_size) {
+			return None;
+		}
+		let url = self.url.clone()?;
+		let entry = cache::get(&url);
+		if let Some(entry) = &entry {
+			for (header, value) in entry.conditional_headers() {
+				self.set_header(header, value);
+			}
+		}
+		Some((url, entry))
+	}
+
+	/// Request a range of bytes of the body in a builder, by setting the `Range` header.
+	///
+	/// Servers that support ranges respond with `206 Partial Content`, and the
+	/// returned range can be read with [Response::content_range]. Requests for a
+	/// range don't use the HTTP cache.
+	///
+	/// Returns an [InvalidRange](RequestError::InvalidRange) error if the range is empty.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use aidoku::imports::net::Request;
+	/// // the first kilobyte of the archive
+	/// let header = Request::get("https://example.com/chapter.zip")?
+	///     .range(0..1024)?
+	///     .data()?;
+	/// ```
+	pub fn range<R: RangeBounds<u64>>(mut self, range: R) -> Result<Self, RequestError> {
+		self.set_range(range)?;
+		Ok(self)
+	}
+
+	/// Request a range of bytes of the body, by setting the `Range` header.
+	///
+	/// Returns an [InvalidRange](RequestError::InvalidRange) error if the range is empty.
+	pub fn set_range<R: RangeBounds<u64>>(&mut self, range: R) -> Result<(), RequestError> {
+		let value = range_header(range).ok_or(RequestError::InvalidRange)?;
+		self.set_header("Range", &value);
+		Ok(())
+	}
+
+	/// Request the last bytes of the body in a builder, e.g. to read the central
+	/// directory at the end of a ZIP archive.
+	///
+	/// Returns an [InvalidRange](RequestError::InvalidRange) error if the length is zero.
+	pub fn range_suffix(mut self, len: u64) -> Result<Self, RequestError> {
+		self.set_range_suffix(len)?;
+		Ok(self)
+	}
+
+	/// Request the last bytes of the body.
+	///
+	/// Returns an [InvalidRange](RequestError::InvalidRange) error if the length is zero.
+	pub fn set_range_suffix(&mut self, len: u64) -> Result<(), RequestError> {
+		if len == 0 {
+			return Err(RequestError::InvalidRange);
+		}
+		self.set_header("Range", &format!("bytes=-{len}"));
+		Ok(())
+	}
+
 	/// Set the HTTP body data in a builder.
 	pub fn body<T: AsRef<[u8]>>(mut self, data: T) -> Self {
 		self.set_body(data);
 		self
 	}
 
+	/// Set the HTTP body to a multipart form in a builder.
+	///
+	/// The `Content-Type` header is set to `multipart/form-data` with the form's boundary.
+	pub fn multipart(mut self, form: MultipartForm) -> Self {
+		self.set_multipart(form);
+		self
+	}
+
+	/// Set the HTTP body to a multipart form.
+	///
+	/// The `Content-Type` header is set to `multipart/form-data` with the form's boundary.
+	pub fn set_multipart(&mut self, form: MultipartForm) {
+		let (content_type, data) = form.build();
+		self.set_header("Content-Type", &content_type);
+		self.set_body(data);
+	}
+
 	/// Set the request timeout interval in a builder.
 	///
 	/// The request timeout interval controls how long (in seconds) a task
@@ -250,6 +1312,23 @@ impl Request {
 		self
 	}
 
+	/// Set how redirects are handled in a builder.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use aidoku::imports::net::{RedirectPolicy, Request};
+	/// // resolve a short link without loading the page it points to
+	/// let response = Request::get("https://example.com/s/abc")?
+	///     .redirects(RedirectPolicy::None)
+	///     .send()?;
+	/// let location = response.get_header("Location");
+	/// ```
+	pub fn redirects(mut self, policy: RedirectPolicy) -> Self {
+		self.set_redirect_policy(policy);
+		self
+	}
+
 	/// Set the HTTP body data.
 	pub fn set_body<T: AsRef<[u8]>>(&mut self, data: T) {
 		let data = data.as_ref();
@@ -264,10 +1343,20 @@ impl Request {
 		unsafe { set_timeout(self.rid, value) };
 	}
 
+	/// Set how redirects are handled.
+	pub fn set_redirect_policy(&mut self, policy: RedirectPolicy) {
+		unsafe { set_redirect_policy(self.rid, policy.ffi_value()) };
+	}
+
 	/// Set the URL for the request.
+	///
+	/// Relative URLs are resolved against the base URL set with [set_base_url].
 	pub fn set_url<T: AsRef<str>>(&mut self, url: T) -> Result<(), RequestError> {
-		let url = url.as_ref();
-		self.url = Some(String::from(url));
+		let url = match with_defaults(|defaults| defaults.base_url.clone()) {
+			Some(base_url) => resolve_url(&base_url, url.as_ref()),
+			None => String::from(url.as_ref()),
+		};
+		self.url = Some(url.clone());
 		let result = unsafe { set_url(self.rid, url.as_ptr(), url.len()) };
 		if let Some(error) = RequestError::from(result) {
 			Err(error)
@@ -282,15 +1371,79 @@ impl Request {
 	}
 
 	/// Send the request.
-	#[inline]
+	///
+	/// If the request has a [RetryPolicy], it's sent again until it succeeds or
+	/// the policy gives up.
 	pub fn send(mut self) -> Result<Response, RequestError> {
-		let result = unsafe { send(self.rid) };
+		let cache = self.prepare_cache();
+		let error = RequestError::from(unsafe { send(self.rid) });
+		self.finish(error, cache)
+	}
+
+	/// Start sending the request in the background, returning a handle that can be
+	/// waited on once the response is needed.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use aidoku::imports::net::Request;
+	/// let chapters = Request::get("https://example.com/manga/1/chapters")?.start()?;
+	/// let details = Request::get("https://example.com/manga/1")?.html()?;
+	/// // ... parse the details while the chapter list is loading
+	/// let chapters = chapters.wait()?.get_html()?;
+	/// ```
+	pub fn start(mut self) -> Result<PendingRequest, RequestError> {
+		let cache = self.prepare_cache();
+		let result = unsafe { start(self.rid) };
 		if let Some(error) = RequestError::from(result) {
-			Err(error)
-		} else {
-			self.should_close = false;
-			Ok(Response::from(self))
+			return Err(error);
+		}
+		Ok(PendingRequest {
+			request: self,
+			cache,
+		})
+	}
+
+	/// Retries the sent request according to its retry policy, and creates the response.
+	fn finish(
+		mut self,
+		mut error: Option<RequestError>,
+		cache: Option<(String, Option<CacheEntry>)>,
+	) -> Result<Response, RequestError> {
+		let mut attempt = 1;
+		while let Some(delay) = self.retry_delay(attempt, error) {
+			sleep(delay as i32);
+			attempt += 1;
+			error = RequestError::from(unsafe { send(self.rid) });
+		}
+		match error {
+			Some(error) => Err(error),
+			None => {
+				self.should_close = false;
+				let mut response = Response::from(self);
+				response.apply_cache(cache);
+				response.check_challenge()?;
+				Ok(response)
+			}
+		}
+	}
+
+	/// Returns the delay before the next attempt, if the request should be retried.
+	fn retry_delay(&self, attempt: u32, error: Option<RequestError>) -> Option<u32> {
+		let policy = self.retry.as_ref()?;
+		let result = match error {
+			Some(error) => Err(error),
+			None => Ok(unsafe { get_status_code(self.rid) }),
+		};
+		if !policy.should_retry(attempt, result) {
+			return None;
 		}
+		let retry_after = if error.is_none() {
+			retry_after(self.rid)
+		} else {
+			None
+		};
+		policy.retry_delay(attempt, retry_after)
 	}
 
 	/// Get the raw data from the response, closing the request.
@@ -316,6 +1469,33 @@ impl Request {
 
 #[cfg(feature = "json")]
 impl Request {
+	/// Set the HTTP body to the value serialized as JSON in a builder.
+	///
+	/// The `Content-Type` header is set to `application/json`.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use aidoku::imports::net::Request;
+	/// let response = Request::post("https://example.com/api/search")?
+	///     .json(&serde_json::json!({ "query": "test", "page": 1 }))?
+	///     .send()?;
+	/// ```
+	pub fn json<T: serde::Serialize + ?Sized>(mut self, value: &T) -> Result<Self, AidokuError> {
+		self.set_json(value)?;
+		Ok(self)
+	}
+
+	/// Set the HTTP body to the value serialized as JSON.
+	///
+	/// The `Content-Type` header is set to `application/json`.
+	pub fn set_json<T: serde::Serialize + ?Sized>(&mut self, value: &T) -> Result<(), AidokuError> {
+		let data = serde_json::to_vec(value)?;
+		self.set_header("Content-Type", "application/json");
+		self.set_body(data);
+		Ok(())
+	}
+
 	/// Get the response data as an owned JSON value.
 	pub fn json_owned<T>(self) -> Result<T, AidokuError>
 	where
@@ -325,10 +1505,52 @@ impl Request {
 	}
 }
 
+#[cfg(feature = "helpers")]
+impl Request {
+	/// Set the HTTP body to the value serialized as a URL-encoded form in a builder.
+	///
+	/// The value is serialized with [QueryParameters::from_data], and the `Content-Type`
+	/// header is set to `application/x-www-form-urlencoded`.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use aidoku::imports::net::Request;
+	///
+	/// #[derive(serde::Serialize)]
+	/// struct Login<'a> {
+	///     username: &'a str,
+	///     password: &'a str,
+	/// }
+	///
+	/// let response = Request::post("https://example.com/login")?
+	///     .form(&Login { username: "user", password: "pass" })?
+	///     .send()?;
+	/// ```
+	pub fn form<T: serde::Serialize>(mut self, value: &T) -> Result<Self, SerializeError> {
+		self.set_form(value)?;
+		Ok(self)
+	}
+
+	/// Set the HTTP body to the value serialized as a URL-encoded form.
+	///
+	/// The value is serialized with [QueryParameters::from_data], and the `Content-Type`
+	/// header is set to `application/x-www-form-urlencoded`.
+	pub fn set_form<T: serde::Serialize>(&mut self, value: &T) -> Result<(), SerializeError> {
+		let query = QueryParameters::from_data(value)?;
+		self.set_header("Content-Type", "application/x-www-form-urlencoded");
+		self.set_body(query.to_string());
+		Ok(())
+	}
+}
+
 impl Response {
 	/// Get the response's status code.
 	#[inline]
 	pub fn status_code(&self) -> i32 {
+		if self.cached.is_some() {
+			return 200;
+		}
 		unsafe { get_status_code(self.rid) }
 	}
 
@@ -341,18 +1563,145 @@ impl Response {
 		read_string_and_destroy(rid)
 	}
 
+	/// Get the URLs that the request was redirected to, in order.
+	///
+	/// The last URL is the final URL of the response, unless redirects were stopped by
+	/// the request's [RedirectPolicy], in which case the next location is in the
+	/// response's `Location` header.
+	pub fn get_redirects(&self) -> Result<Vec<String>, AidokuError> {
+		let rid = unsafe { get_redirects(self.rid) };
+		if let Some(error) = RequestError::from(rid) {
+			return Err(error.into());
+		}
+		let redirects = read(rid);
+		unsafe { destroy(rid) };
+		redirects
+	}
+
 	/// Get a response HTTP header.
 	pub fn get_header<T: AsRef<str>>(&self, header: T) -> Option<String> {
 		let header = header.as_ref();
 		let rid = unsafe { get_header(self.rid, header.as_ptr(), header.len()) };
 		if rid < 0 {
-			return None;
+			// a not modified response may not include the content type
+			return self
+				.cached
+				.as_ref()
+				.filter(|_| header.eq_ignore_ascii_case("Content-Type"))
+				.and_then(|entry| entry.content_type.clone());
 		}
 		read_string_and_destroy(rid)
 	}
 
+	/// Uses the cached response if the server responded with `304 Not Modified`, or
+	/// otherwise marks the response to be cached once its body is read.
+	fn apply_cache(&mut self, cache: Option<(String, Option<CacheEntry>)>) {
+		let Some((url, entry)) = cache else {
+			return;
+		};
+		match cache::update(self.status_code(), entry, |header| self.get_header(header)) {
+			CacheUpdate::UseStored(entry) => {
+				let max_size = with_defaults(|defaults| defaults.cache_size);
+				cache::touch(&url, entry.data.len(), max_size);
+				self.cached = Some(entry);
+			}
+			CacheUpdate::Store(entry) => *self.uncached.get_mut() = Some((url, entry)),
+			CacheUpdate::None => {}
+		}
+	}
+
+	/// Returns an error if the response is a bot protection challenge.
+	fn check_challenge(&self) -> Result<(), RequestError> {
+		let status_code = self.status_code();
+		if !CHALLENGE_STATUS_CODES.contains(&status_code) {
+			return Ok(());
+		}
+		let server = self.get_header("Server");
+		let mitigated = self.get_header("cf-mitigated");
+		// the body is only read for responses from bot protection servers
+		let challenge =
+			challenge_from_headers(status_code, server.as_deref(), mitigated.as_deref())
+				.unwrap_or_else(|| has_challenge_marker(&self.get_data().unwrap_or_default()));
+		if challenge {
+			Err(RequestError::Challenge)
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Get all response HTTP headers.
+	pub fn get_headers(&self) -> Result<Headers, AidokuError> {
+		let rid = unsafe { get_headers(self.rid) };
+		if let Some(error) = RequestError::from(rid) {
+			return Err(error.into());
+		}
+		let entries = read(rid);
+		unsafe { destroy(rid) };
+		Ok(Headers::new(entries?))
+	}
+
+	/// Get the cookies set by the response.
+	pub fn get_cookies(&self) -> Result<Vec<Cookie>, AidokuError> {
+		let url = self
+			.get_url()
+			.or_else(|| self.url.clone())
+			.unwrap_or_default();
+		let domain = url_host(&url).unwrap_or_default().to_ascii_lowercase();
+		Ok(self.get_headers()?.cookies(&domain, current_date()))
+	}
+
+	/// Get the length of the response body in bytes.
+	pub fn data_len(&self) -> Result<usize, RequestError> {
+		if let Some(entry) = &self.cached {
+			return Ok(entry.data.len());
+		}
+		let size = unsafe { data_len(self.rid) };
+		if let Some(error) = RequestError::from(size) {
+			return Err(error);
+		}
+		Ok(size as usize)
+	}
+
+	/// Read part of the response body, starting at the given offset, into the buffer.
+	///
+	/// Returns the number of bytes read, which is zero at the end of the body.
+	pub fn read_data_at(&self, offset: usize, buffer: &mut [u8]) -> Result<usize, RequestError> {
+		if let Some(entry) = &self.cached {
+			let data = entry.data.get(offset..).unwrap_or_default();
+			let len = data.len().min(buffer.len());
+			buffer[..len].copy_from_slice(&data[..len]);
+			return Ok(len);
+		}
+		let result = unsafe { read_data_at(self.rid, offset, buffer.as_mut_ptr(), buffer.len()) };
+		if let Some(error) = RequestError::from(result) {
+			return Err(error);
+		}
+		Ok(result as usize)
+	}
+
+	/// Iterate over the response body in chunks of the given size, without copying the
+	/// whole body into memory at once.
+	pub fn chunks(&self, chunk_size: usize) -> DataChunks<'_> {
+		DataChunks {
+			response: self,
+			offset: 0,
+			chunk_size: chunk_size.max(1),
+			finished: false,
+		}
+	}
+
+	/// Get the range of the body in a `206 Partial Content` response.
+	pub fn content_range(&self) -> Option<ContentRange> {
+		ContentRange::parse(&self.get_header("Content-Range")?)
+	}
+
 	/// Get the raw data from the response.
+	///
+	/// If the request uses the HTTP cache, the data is cached once it's read.
 	pub fn get_data(&self) -> Result<Vec<u8>, RequestError> {
+		if let Some(entry) = &self.cached {
+			return Ok(entry.data.clone());
+		}
 		let size = unsafe { data_len(self.rid) };
 		if let Some(error) = RequestError::from(size) {
 			return Err(error);
@@ -366,11 +1715,19 @@ impl Response {
 			}
 			buffer.set_len(size);
 		}
+		if let Some((url, mut entry)) = self.uncached.take() {
+			entry.data = buffer;
+			cache::insert(&url, &entry, with_defaults(|defaults| defaults.cache_size));
+			buffer = entry.data;
+		}
 		Ok(buffer)
 	}
 
 	/// Gets the response data as an image.
 	pub fn get_image(&self) -> Result<ImageRef, RequestError> {
+		if let Some(entry) = &self.cached {
+			return Ok(ImageRef::new(&entry.data));
+		}
 		let result = unsafe { get_image(self.rid) };
 		if let Some(error) = RequestError::from(result) {
 			Err(error)
@@ -380,16 +1737,41 @@ impl Response {
 	}
 
 	/// Gets the response data as a string.
+	///
+	/// With the `encoding` feature, the data is decoded using the encoding set with
+	/// [Response::with_encoding], or otherwise the charset from the `Content-Type` header
+	/// or a `<meta charset>` tag. Without it, the data must be valid UTF-8.
 	pub fn get_string(&self) -> Result<String, AidokuError> {
-		let res = String::from_utf8(self.get_data()?);
-		match res {
-			Ok(res) => Ok(res),
-			Err(err) => Err(AidokuError::Utf8Error(err.utf8_error())),
+		let data = self.get_data()?;
+		#[cfg(feature = "encoding")]
+		{
+			let encoding = self.encoding.unwrap_or_else(|| {
+				encoding::detect(&data, self.get_header("Content-Type").as_deref())
+			});
+			Ok(encoding::decode_with(&data, encoding))
 		}
+		#[cfg(not(feature = "encoding"))]
+		String::from_utf8(data).map_err(|err| AidokuError::Utf8Error(err.utf8_error()))
 	}
 
 	/// Get the response data as an HTML [Document].
+	///
+	/// The document is decoded the same way as [Response::get_string].
 	pub fn get_html(&self) -> Result<Document, RequestError> {
+		// responses that will be cached are parsed here, so that the body is only read once
+		#[cfg(feature = "encoding")]
+		let decode_locally =
+			self.cached.is_some() || self.uncached.borrow().is_some() || self.encoding.is_some();
+		#[cfg(not(feature = "encoding"))]
+		let decode_locally = self.cached.is_some() || self.uncached.borrow().is_some();
+		if decode_locally {
+			let text = self.get_string().map_err(|_| RequestError::InvalidHtml)?;
+			let url = self
+				.get_url()
+				.or_else(|| self.url.clone())
+				.unwrap_or_default();
+			return Html::parse_with_url(text, url).map_err(|_| RequestError::InvalidHtml);
+		}
 		let rid = unsafe { html(self.rid) };
 		if let Some(error) = RequestError::from(rid) {
 			return Err(error);
@@ -399,16 +1781,12 @@ impl Response {
 
 	/// Create a new request with the same method and url as the one sent to get this response.
 	pub fn into_request(self) -> Request {
-		let rid = unsafe { init(self.http_method) };
+		let mut request = Request::init(self.http_method);
 		if let Some(url) = self.url.as_ref() {
-			_ = unsafe { set_url(rid, url.as_ptr(), url.len()) };
-		}
-		Request {
-			rid,
-			http_method: self.http_method,
-			url: self.url.clone(),
-			should_close: true,
+			_ = unsafe { set_url(request.rid, url.as_ptr(), url.len()) };
+			request.url = Some(url.clone());
 		}
+		request
 	}
 }
 
@@ -436,6 +1814,25 @@ impl Response {
 	}
 }
 
+#[cfg(feature = "encoding")]
+impl Response {
+	/// Decode the response with the given encoding, instead of detecting it.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use aidoku::{helpers::encoding::SHIFT_JIS, imports::net::Request};
+	/// let text = Request::get("https://example.com")?
+	///     .send()?
+	///     .with_encoding(SHIFT_JIS)
+	///     .get_string()?;
+	/// ```
+	pub fn with_encoding(mut self, encoding: &'static Encoding) -> Self {
+		self.encoding = Some(encoding);
+		self
+	}
+}
+
 impl Response {
 	// don't implement From<Request> here since this should stay private
 	fn from(request: Request) -> Self {
@@ -444,6 +1841,10 @@ impl Response {
 			http_method: request.http_method,
 			url: request.url.clone(),
 			data: None,
+			cached: None,
+			uncached: RefCell::new(None),
+			#[cfg(feature = "encoding")]
+			encoding: None,
 		}
 	}
 }
@@ -470,3 +1871,139 @@ impl Drop for Response {
 pub fn set_rate_limit(permits: i32, period: i32, unit: TimeUnit) {
 	unsafe { net_set_rate_limit(permits, period, unit.into()) }
 }
+
+/// Set the number of requests allowed per a given time period for a host or URL pattern.
+///
+/// The pattern is either a host, which can start with `*.` to match all subdomains, or a
+/// URL prefix like `https://example.com/api/`. If multiple patterns match a request, the
+/// longest one is used. Requests have to wait for both the matching limit and the limit
+/// set with [set_rate_limit].
+///
+/// Setting zero permits removes the limit for the pattern. Negative permits or periods
+/// return an [InvalidRateLimit](RequestError::InvalidRateLimit) error.
+///
+/// # Examples
+///
+/// ```ignore
+/// use aidoku::imports::net::{TimeUnit, set_host_rate_limit};
+/// // 2 api requests per second, and 30 image requests per minute
+/// set_host_rate_limit("api.example.com", 2, 1, TimeUnit::Seconds)?;
+/// set_host_rate_limit("*.cdn.example.com", 30, 1, TimeUnit::Minutes)?;
+/// ```
+pub fn set_host_rate_limit<T: AsRef<str>>(
+	pattern: T,
+	permits: i32,
+	period: i32,
+	unit: TimeUnit,
+) -> Result<(), RequestError> {
+	let pattern = pattern.as_ref();
+	let result = unsafe {
+		net_set_host_rate_limit(
+			pattern.as_ptr(),
+			pattern.len(),
+			permits,
+			period,
+			unit.into(),
+		)
+	};
+	if let Some(error) = RequestError::from(result) {
+		Err(error)
+	} else {
+		Ok(())
+	}
+}
+
+/// Get the cookies for a domain from the app's shared cookie store.
+///
+/// Cookies of parent domains are included, e.g. cookies for `example.com` are
+/// returned for `www.example.com`.
+pub fn get_cookies<T: AsRef<str>>(domain: T) -> Result<Vec<Cookie>, AidokuError> {
+	let domain = domain.as_ref();
+	let rid = unsafe { net_get_cookies(domain.as_ptr(), domain.len()) };
+	if let Some(error) = RequestError::from(rid) {
+		return Err(error.into());
+	}
+	let cookies = read(rid);
+	unsafe { destroy(rid) };
+	cookies
+}
+
+/// Get the value of a cookie for a domain from the app's shared cookie store.
+pub fn get_cookie<T: AsRef<str>, U: AsRef<str>>(name: T, domain: U) -> Option<String> {
+	get_cookies(domain)
+		.ok()?
+		.into_iter()
+		.find(|cookie| cookie.name == name.as_ref())
+		.map(|cookie| cookie.value)
+}
+
+/// Add a cookie to the app's shared cookie store, which is sent with matching requests.
+///
+/// A cookie with the same name, domain and path is replaced.
+///
+/// # Examples
+///
+/// ```ignore
+/// use aidoku::imports::net::{Cookie, set_cookie};
+/// set_cookie(&Cookie::new("age_verified", "1", "example.com"))?;
+/// ```
+pub fn set_cookie(cookie: &Cookie) -> Result<(), RequestError> {
+	// serializing a cookie can't fail
+	let data = postcard::to_allocvec(cookie).unwrap_or_default();
+	let result = unsafe { net_set_cookie(data.as_ptr(), data.len()) };
+	if let Some(error) = RequestError::from(result) {
+		Err(error)
+	} else {
+		Ok(())
+	}
+}
+
+/// Delete a cookie for a domain from the app's shared cookie store.
+pub fn delete_cookie<T: AsRef<str>, U: AsRef<str>>(name: T, domain: U) -> Result<(), RequestError> {
+	let name = name.as_ref();
+	let domain = domain.as_ref();
+	let result =
+		unsafe { net_delete_cookie(name.as_ptr(), name.len(), domain.as_ptr(), domain.len()) };
+	if let Some(error) = RequestError::from(result) {
+		Err(error)
+	} else {
+		Ok(())
+	}
+}
+
+/// Delete all cookies for a domain from the app's shared cookie store.
+pub fn delete_all_cookies<T: AsRef<str>>(domain: T) -> Result<(), RequestError> {
+	let domain = domain.as_ref();
+	let result = unsafe { net_delete_cookies(domain.as_ptr(), domain.len()) };
+	if let Some(error) = RequestError::from(result) {
+		Err(error)
+	} else {
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_range_header() {
+		assert_eq!(range_header(0..1024).as_deref(), Some("bytes=0-1023"));
+		assert_eq!(range_header(5..=5).as_deref(), Some("bytes=5-5"));
+		assert_eq!(range_header(100..).as_deref(), Some("bytes=100-"));
+		assert_eq!(range_header(..10).as_deref(), Some("bytes=0-9"));
+		assert_eq!(range_header(..).as_deref(), Some("bytes=0-"));
+
+		// empty ranges are invalid
+		assert_eq!(range_header(..0), None);
+		assert_eq!(range_header(5..5), None);
+		assert_eq!(
+			range_header((Bound::Included(10), Bound::Included(5))),
+			None
+		);
+		assert_eq!(
+			range_header((Bound::Excluded(u64::MAX), Bound::Unbounded)),
+			None
+		);
+	}
+}
@@ -0,0 +1,281 @@
+//! A persistent HTTP cache for conditional requests.
+//!
+//! Responses with an `ETag` or `Last-Modified` header are stored in the defaults store.
+//! When the same URL is requested again, the validators are sent with `If-None-Match` and
+//! `If-Modified-Since`, and a `304 Not Modified` response is answered with the stored body.
+//!
+//! Responses are only stored once their body is read, so the body isn't copied for
+//! responses that are never read or are read in parts.
+use super::HttpMethod;
+use crate::{
+	alloc::{String, Vec, format},
+	imports::defaults::{DefaultValue, defaults_get, defaults_set, defaults_set_data},
+};
+use serde::{Deserialize, Serialize};
+
+/// The defaults key of the cache index.
+const INDEX_KEY: &str = "aidoku.http_cache";
+
+/// A stored response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+	pub etag: Option<String>,
+	pub last_modified: Option<String>,
+	pub content_type: Option<String>,
+	pub data: Vec<u8>,
+}
+
+impl CacheEntry {
+	/// The headers that make a request for the stored response conditional.
+	pub(super) fn conditional_headers(&self) -> Vec<(&'static str, &str)> {
+		let mut headers = Vec::new();
+		if let Some(etag) = &self.etag {
+			headers.push(("If-None-Match", etag.as_str()));
+		}
+		if let Some(last_modified) = &self.last_modified {
+			headers.push(("If-Modified-Since", last_modified.as_str()));
+		}
+		headers
+	}
+}
+
+/// How the response to a request that uses the cache is handled.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum CacheUpdate {
+	/// The stored response is still valid, and is used in place of the response.
+	UseStored(CacheEntry),
+	/// The response can be stored once its body is read.
+	Store(CacheEntry),
+	/// The response can't be cached.
+	None,
+}
+
+/// Returns true if a request uses the cache.
+///
+/// Requests for a range of the body don't use it, since only full responses are stored.
+pub(super) fn uses_cache(enabled: bool, method: HttpMethod, ranged: bool, max_size: usize) -> bool {
+	enabled && !ranged && max_size > 0 && method == HttpMethod::Get
+}
+
+/// Returns how a response is handled, given its status code, the stored response for
+/// its URL, and a function that gets its headers.
+pub(super) fn update<F>(status: i32, stored: Option<CacheEntry>, get_header: F) -> CacheUpdate
+where
+	F: Fn(&str) -> Option<String>,
+{
+	match (status, stored) {
+		(304, Some(entry)) => CacheUpdate::UseStored(entry),
+		(200, _) => {
+			let etag = get_header("ETag");
+			let last_modified = get_header("Last-Modified");
+			if etag.is_none() && last_modified.is_none() {
+				return CacheUpdate::None;
+			}
+			CacheUpdate::Store(CacheEntry {
+				etag,
+				last_modified,
+				content_type: get_header("Content-Type"),
+				data: Vec::new(),
+			})
+		}
+		_ => CacheUpdate::None,
+	}
+}
+
+/// The URLs and sizes of the stored responses, least recently used first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct CacheIndex {
+	entries: Vec<(String, usize)>,
+}
+
+impl CacheIndex {
+	/// The total size of the stored responses in bytes.
+	pub fn size(&self) -> usize {
+		self.entries.iter().map(|(_, size)| size).sum()
+	}
+
+	pub fn contains(&self, url: &str) -> bool {
+		self.entries.iter().any(|(key, _)| key == url)
+	}
+
+	/// Marks the entry for a URL as most recently used, with the given size.
+	///
+	/// Returns the URLs of the least recently used entries that were removed to keep
+	/// the total size within `max_size`.
+	pub fn insert(&mut self, url: &str, size: usize, max_size: usize) -> Vec<String> {
+		self.remove(url);
+		self.entries.push((url.into(), size));
+		let mut evicted = Vec::new();
+		while self.size() > max_size && !self.entries.is_empty() {
+			evicted.push(self.entries.remove(0).0);
+		}
+		evicted
+	}
+
+	/// Removes the entry for a URL, returning true if it existed.
+	pub fn remove(&mut self, url: &str) -> bool {
+		let len = self.entries.len();
+		self.entries.retain(|(key, _)| key != url);
+		self.entries.len() != len
+	}
+
+	/// Removes all entries, returning their URLs.
+	pub fn clear(&mut self) -> Vec<String> {
+		self.entries.drain(..).map(|(url, _)| url).collect()
+	}
+}
+
+fn entry_key(url: &str) -> String {
+	format!("{INDEX_KEY}.{url}")
+}
+
+fn load_index() -> CacheIndex {
+	defaults_get(INDEX_KEY).unwrap_or_default()
+}
+
+fn remove_entries(urls: Vec<String>) {
+	for url in urls {
+		defaults_set(&entry_key(&url), DefaultValue::Null);
+	}
+}
+
+/// Returns the stored response for a URL.
+pub(super) fn get(url: &str) -> Option<CacheEntry> {
+	if !load_index().contains(url) {
+		return None;
+	}
+	defaults_get(&entry_key(url))
+}
+
+/// Stores a response, evicting the least recently used responses if the cache is full.
+pub(super) fn insert(url: &str, entry: &CacheEntry, max_size: usize) {
+	let mut index = load_index();
+	if entry.data.len() > max_size {
+		// too large to be cached, so remove any outdated entry
+		if index.remove(url) {
+			defaults_set_data(INDEX_KEY, &index);
+			remove_entries(Vec::from([url.into()]));
+		}
+		return;
+	}
+	let evicted = index.insert(url, entry.data.len(), max_size);
+	defaults_set_data(&entry_key(url), entry);
+	defaults_set_data(INDEX_KEY, &index);
+	remove_entries(evicted);
+}
+
+/// Marks the stored response for a URL as recently used.
+pub(super) fn touch(url: &str, size: usize, max_size: usize) {
+	let mut index = load_index();
+	let evicted = index.insert(url, size, max_size);
+	defaults_set_data(INDEX_KEY, &index);
+	remove_entries(evicted);
+}
+
+/// Removes all stored responses.
+pub fn clear_cache() {
+	let mut index = load_index();
+	let urls = index.clear();
+	defaults_set_data(INDEX_KEY, &index);
+	remove_entries(urls);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::alloc::{string::ToString, vec};
+
+	fn entry() -> CacheEntry {
+		CacheEntry {
+			etag: Some("\"abc\"".into()),
+			last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".into()),
+			content_type: Some("text/html".into()),
+			data: b"body".to_vec(),
+		}
+	}
+
+	#[test]
+	fn test_cache_index() {
+		let mut index = CacheIndex::default();
+		assert!(index.insert("a", 40, 100).is_empty());
+		assert!(index.insert("b", 40, 100).is_empty());
+		assert_eq!(index.size(), 80);
+
+		// using an entry makes it the most recently used
+		assert!(index.insert("a", 40, 100).is_empty());
+		assert_eq!(index.insert("c", 40, 100), ["b"]);
+		assert!(index.contains("a") && index.contains("c") && !index.contains("b"));
+
+		// a replaced entry updates its size
+		assert!(index.insert("c", 10, 100).is_empty());
+		assert_eq!(index.size(), 50);
+
+		assert_eq!(index.insert("d", 100, 100), ["a", "c"]);
+		assert_eq!(index.entries.len(), 1);
+
+		assert!(index.remove("d"));
+		assert!(!index.remove("d"));
+		assert!(index.entries.is_empty());
+
+		index.insert("e", 1, 100);
+		index.insert("f", 1, 100);
+		assert_eq!(index.clear(), ["e", "f"]);
+		assert_eq!(index.size(), 0);
+	}
+
+	#[test]
+	fn test_uses_cache() {
+		assert!(uses_cache(true, HttpMethod::Get, false, 100));
+		// requests can opt out with `cache(false)`
+		assert!(!uses_cache(false, HttpMethod::Get, false, 100));
+		assert!(!uses_cache(true, HttpMethod::Get, false, 0));
+		assert!(!uses_cache(true, HttpMethod::Post, false, 100));
+		assert!(!uses_cache(true, HttpMethod::Get, true, 100));
+	}
+
+	#[test]
+	fn test_conditional_headers() {
+		let entry = entry();
+		assert_eq!(
+			entry.conditional_headers(),
+			[
+				("If-None-Match", "\"abc\""),
+				("If-Modified-Since", "Wed, 21 Oct 2015 07:28:00 GMT")
+			]
+		);
+		let entry = CacheEntry {
+			last_modified: None,
+			..entry
+		};
+		assert_eq!(entry.conditional_headers(), [("If-None-Match", "\"abc\"")]);
+	}
+
+	#[test]
+	fn test_update() {
+		let headers = |header: &str| match header {
+			"ETag" => Some("\"abc\"".to_string()),
+			"Content-Type" => Some("text/html".to_string()),
+			_ => None,
+		};
+
+		// a not modified response is served from the cache
+		assert_eq!(
+			update(304, Some(entry()), headers),
+			CacheUpdate::UseStored(entry())
+		);
+		assert_eq!(update(304, None, headers), CacheUpdate::None);
+
+		// a new response is stored with its validators once its body is read
+		assert_eq!(
+			update(200, Some(entry()), headers),
+			CacheUpdate::Store(CacheEntry {
+				etag: Some("\"abc\"".into()),
+				last_modified: None,
+				content_type: Some("text/html".into()),
+				data: vec![],
+			})
+		);
+		assert_eq!(update(200, None, |_| None), CacheUpdate::None);
+		assert_eq!(update(404, None, headers), CacheUpdate::None);
+	}
+}
@@ -0,0 +1,161 @@
+//! Module for snapshot testing source results.
+//!
+//! Values are serialized to JSON and sent to the test runner, which compares them against
+//! snapshot files stored in the `snapshots` directory of the crate. Missing snapshots are
+//! created, and existing snapshots are updated when the `AIDOKU_UPDATE_SNAPSHOTS` environment
+//! variable is set.
+//!
+//! # Example
+//! ```ignore
+//! use aidoku::imports::snapshot::{assert_snapshot, assert_snapshot_redacted};
+//!
+//! #[aidoku_test]
+//! fn parse_search() {
+//!     let result = parse_search_page(include_str!("../fixtures/search.html"));
+//!     assert_snapshot("search", &result);
+//!
+//!     let manga = parse_manga_page(include_str!("../fixtures/manga.html"));
+//!     assert_snapshot_redacted("manga", &manga, &[".chapters[].date_uploaded"]);
+//! }
+//! ```
+use super::FFIResult;
+use crate::alloc::{String, Vec};
+use serde::Serialize;
+use serde_json::Value;
+
+#[link(wasm_import_module = "test")]
+unsafe extern "C" {
+	#[link_name = "assert_snapshot"]
+	fn _assert_snapshot(
+		name_ptr: *const u8,
+		name_len: usize,
+		data_ptr: *const u8,
+		data_len: usize,
+	) -> FFIResult;
+}
+
+/// Error type for snapshot assertions.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SnapshotError {
+	InvalidString,
+	InvalidJson,
+	Mismatch,
+	FailedWrite,
+}
+
+impl SnapshotError {
+	fn from(value: FFIResult) -> Option<Self> {
+		match value {
+			-1 => Some(Self::InvalidString),
+			-2 => Some(Self::InvalidJson),
+			-3 => Some(Self::Mismatch),
+			-4 => Some(Self::FailedWrite),
+			_ => None,
+		}
+	}
+}
+
+/// The value that redacted fields are replaced with.
+pub const REDACTED: &str = "[redacted]";
+
+/// Asserts that the value matches the stored snapshot with the given name.
+///
+/// # Panics
+///
+/// Panics if the snapshot doesn't match, or if the value can't be serialized.
+pub fn assert_snapshot<T: Serialize + ?Sized>(name: &str, value: &T) {
+	assert_snapshot_redacted(name, value, &[]);
+}
+
+/// Asserts that the value matches the stored snapshot, after replacing the fields matching
+/// the given selectors with a placeholder.
+///
+/// Selectors are paths of fields separated by `.`, where `[]` matches every item of an array
+/// and `[n]` matches a single item, e.g. `.entries[].chapters[0].date_uploaded`.
+///
+/// # Panics
+///
+/// Panics if the snapshot doesn't match, or if the value can't be serialized.
+pub fn assert_snapshot_redacted<T: Serialize + ?Sized>(name: &str, value: &T, redactions: &[&str]) {
+	let mut value = serde_json::to_value(value).expect("failed to serialize snapshot value");
+	for selector in redactions {
+		redact(&mut value, selector);
+	}
+	let data = serde_json::to_vec(&value).expect("failed to serialize snapshot value");
+	let result = unsafe { _assert_snapshot(name.as_ptr(), name.len(), data.as_ptr(), data.len()) };
+	if let Some(error) = SnapshotError::from(result) {
+		match error {
+			SnapshotError::Mismatch => panic!("snapshot `{name}` does not match"),
+			error => panic!("failed to check snapshot `{name}`: {error:?}"),
+		}
+	}
+}
+
+enum Segment {
+	Field(String),
+	Index(usize),
+	All,
+}
+
+fn parse_selector(selector: &str) -> Vec<Segment> {
+	let mut segments = Vec::new();
+	for part in selector.split('.').filter(|part| !part.is_empty()) {
+		let (field, mut rest) = match part.find('[') {
+			Some(idx) => part.split_at(idx),
+			None => (part, ""),
+		};
+		if !field.is_empty() {
+			segments.push(Segment::Field(field.into()));
+		}
+		while let Some(end) = rest.find(']') {
+			let index = rest[1..end].trim();
+			if index.is_empty() || index == "*" {
+				segments.push(Segment::All);
+			} else if let Ok(index) = index.parse() {
+				segments.push(Segment::Index(index));
+			} else {
+				segments.push(Segment::Field(index.into()));
+			}
+			rest = &rest[end + 1..];
+		}
+	}
+	segments
+}
+
+/// Replaces the values matching the selector with [REDACTED].
+///
+/// Null values are left as is, so missing fields still show up in snapshots.
+pub fn redact(value: &mut Value, selector: &str) {
+	fn apply(value: &mut Value, segments: &[Segment]) {
+		let Some((segment, rest)) = segments.split_first() else {
+			if !value.is_null() {
+				*value = Value::String(REDACTED.into());
+			}
+			return;
+		};
+		match (segment, value) {
+			(Segment::Field(name), Value::Object(map)) => {
+				if let Some(value) = map.get_mut(name) {
+					apply(value, rest);
+				}
+			}
+			(Segment::Index(idx), Value::Array(items)) => {
+				if let Some(value) = items.get_mut(*idx) {
+					apply(value, rest);
+				}
+			}
+			(Segment::All, Value::Array(items)) => {
+				for value in items {
+					apply(value, rest);
+				}
+			}
+			(Segment::All, Value::Object(map)) => {
+				for value in map.values_mut() {
+					apply(value, rest);
+				}
+			}
+			_ => {}
+		}
+	}
+	apply(value, &parse_selector(selector));
+}
@@ -112,6 +112,9 @@ macro_rules! register_source {
 			$crate::prelude::println!("Error: {:?}", error);
 			match error {
 				$crate::imports::error::AidokuError::Unimplemented => -2,
+				$crate::imports::error::AidokuError::RequestError(
+					$crate::imports::net::RequestError::Challenge,
+				) => -10,
 				$crate::imports::error::AidokuError::RequestError(_) => -3,
 				$crate::imports::error::AidokuError::HtmlError(_) => -4,
 				$crate::imports::error::AidokuError::JsError(_) => -5,
@@ -119,6 +122,10 @@ macro_rules! register_source {
 				$crate::imports::error::AidokuError::Utf8Error(_) => -7,
 				$crate::imports::error::AidokuError::JsonParseError(_) => -8,
 				$crate::imports::error::AidokuError::DeserializeError => -9,
+				// the server's messages are shown in the app
+				$crate::imports::error::AidokuError::GraphqlError(messages) => __handle_error(
+					&$crate::imports::error::AidokuError::Message(messages.join("\n")),
+				),
 				$crate::imports::error::AidokuError::Message(string) => {
 					let mut buffer = (-1 as i32).to_le_bytes().to_vec();
 
@@ -0,0 +1,137 @@
+#![cfg(feature = "helpers")]
+
+use aidoku::{
+	Chapter, Manga, MangaPageResult,
+	helpers::contract::{Severity, Validate, assert_valid, assert_valid_with},
+};
+
+fn paths(value: &impl Validate, severity: Severity) -> Vec<String> {
+	value
+		.validate()
+		.into_iter()
+		.filter(|violation| violation.severity == severity)
+		.map(|violation| violation.path)
+		.collect()
+}
+
+#[test]
+fn valid_manga() {
+	let manga = Manga {
+		key: "1".into(),
+		title: "Title".into(),
+		cover: Some("https://example.com/cover.jpg".into()),
+		url: Some("https://example.com/manga/1".into()),
+		tags: Some(vec!["Action".into()]),
+		chapters: Some(vec![
+			Chapter {
+				key: "1".into(),
+				..Default::default()
+			},
+			Chapter {
+				key: "2".into(),
+				chapter_number: Some(2.0),
+				..Default::default()
+			},
+		]),
+		..Default::default()
+	};
+	assert!(manga.validate().is_empty());
+	assert_valid(&manga);
+}
+
+#[test]
+fn invalid_manga() {
+	let manga = Manga {
+		key: "".into(),
+		title: " ".into(),
+		cover: Some("/cover.jpg".into()),
+		url: Some("https:///manga".into()),
+		tags: Some((0..256).map(|i| i.to_string()).chain(["".into()]).collect()),
+		chapters: Some(vec![
+			Chapter {
+				key: "1".into(),
+				chapter_number: Some(-1.0),
+				..Default::default()
+			},
+			Chapter {
+				key: "1".into(),
+				thumbnail: Some("thumb.jpg".into()),
+				..Default::default()
+			},
+		]),
+		..Default::default()
+	};
+	assert_eq!(
+		paths(&manga, Severity::Error),
+		[
+			"key",
+			"title",
+			"cover",
+			"url",
+			"tags",
+			"chapters[1].thumbnail",
+			"chapters[1].key"
+		]
+	);
+	assert_eq!(
+		paths(&manga, Severity::Warning),
+		["tags[256]", "chapters[0].chapter_number"]
+	);
+}
+
+#[test]
+#[should_panic(expected = "chapters[1].key: duplicate chapter key `a`")]
+fn assert_valid_panics() {
+	let chapters = vec![
+		Chapter {
+			key: "a".into(),
+			..Default::default()
+		},
+		Chapter {
+			key: "a".into(),
+			..Default::default()
+		},
+	];
+	let manga = Manga {
+		key: "1".into(),
+		title: "Title".into(),
+		chapters: Some(chapters),
+		..Default::default()
+	};
+	assert_valid(&manga);
+}
+
+#[test]
+fn assert_valid_warnings() {
+	let result = MangaPageResult {
+		entries: Vec::new(),
+		has_next_page: true,
+	};
+	// warnings are reported, but don't fail
+	let mut warnings = Vec::new();
+	assert_valid_with(&result, |violation| warnings.push(violation.to_string()));
+	assert_eq!(warnings.len(), 1);
+	assert!(warnings[0].starts_with("warning: has_next_page: "));
+	assert_valid(&result);
+}
+
+#[test]
+fn manga_page_result() {
+	let result = MangaPageResult {
+		entries: Vec::new(),
+		has_next_page: true,
+	};
+	assert_eq!(paths(&result, Severity::Warning), ["has_next_page"]);
+
+	let manga = Manga {
+		key: "1".into(),
+		title: "Title".into(),
+		..Default::default()
+	};
+	let result = MangaPageResult {
+		entries: vec![manga.clone(), manga],
+		has_next_page: false,
+	};
+	assert_eq!(paths(&result, Severity::Warning), ["entries[1].key"]);
+	assert!(paths(&result, Severity::Error).is_empty());
+}
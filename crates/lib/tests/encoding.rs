@@ -0,0 +1,60 @@
+#![cfg(feature = "encoding")]
+use aidoku::helpers::encoding::*;
+
+// "漫画" in shift_jis
+const SHIFT_JIS_TEXT: &[u8] = b"\x96\x9f\x89\xe6";
+
+#[test]
+fn test_from_content_type() {
+	assert_eq!(
+		from_content_type("text/html; charset=Shift_JIS"),
+		Some(SHIFT_JIS)
+	);
+	assert_eq!(
+		from_content_type("text/html;charset=\"windows-1251\""),
+		Some(WINDOWS_1251)
+	);
+	assert_eq!(from_content_type("text/html"), None);
+	assert_eq!(from_content_type("text/html; charset=unknown"), None);
+}
+
+#[test]
+fn test_from_meta() {
+	assert_eq!(
+		from_meta(b"<html><head><META CHARSET=\"euc-kr\"></head>"),
+		Some(EUC_KR)
+	);
+	assert_eq!(
+		from_meta(
+			b"<meta name=\"a\"><meta http-equiv=\"Content-Type\" content=\"text/html; charset=gbk\">"
+		),
+		Some(GBK)
+	);
+	assert_eq!(from_meta(b"<meta charset=utf-16>"), Some(UTF_8));
+	assert_eq!(from_meta(b"<meta name=\"charset\">"), None);
+	assert_eq!(from_meta(b"<p>charset=gbk</p>"), None);
+}
+
+#[test]
+fn test_decode() {
+	let mut html = b"<meta charset=\"shift_jis\"><p>".to_vec();
+	html.extend_from_slice(SHIFT_JIS_TEXT);
+	assert_eq!(decode(&html, None), "<meta charset=\"shift_jis\"><p>漫画");
+	// the content type takes precedence over the meta tag
+	assert_eq!(
+		decode(SHIFT_JIS_TEXT, Some("text/html; charset=shift_jis")),
+		"漫画"
+	);
+	// valid utf-8 is not decoded with the meta charset
+	assert_eq!(
+		decode("<meta charset=\"shift_jis\">漫画".as_bytes(), None),
+		"<meta charset=\"shift_jis\">漫画"
+	);
+	// a byte order mark takes precedence over everything else
+	assert_eq!(
+		decode(b"\xef\xbb\xbfa", Some("text/plain; charset=gbk")),
+		"a"
+	);
+	assert_eq!(decode_with(SHIFT_JIS_TEXT, SHIFT_JIS), "漫画");
+	assert_eq!(for_label(" Shift_JIS "), Some(SHIFT_JIS));
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "imports")]
+use aidoku::imports::html::FromElement;
+use aidoku_derive::FromElement;
+use core::str::FromStr;
+
+// the structs are generic so that their implementations are only type checked, since
+// extracting values needs the html imports
+
+#[allow(dead_code)]
+#[derive(FromElement)]
+struct Chapter<Id: FromStr> {
+	#[html(attr = "data-id", parse)]
+	id: Id,
+	#[html(select = "a", attr = "abs:href")]
+	url: String,
+	#[html(own_text, trim)]
+	title: String,
+}
+
+#[allow(dead_code)]
+#[derive(FromElement)]
+struct Manga<Id: FromStr> {
+	#[html(select = ".title")]
+	title: String,
+	#[html(select = ".description", html, trim)]
+	description: Option<String>,
+	#[html(select = ".tags a")]
+	tags: Vec<String>,
+	#[html(select = ".rating", parse)]
+	rating: Option<f32>,
+	#[html(select = ".chapter", nested)]
+	chapters: Vec<Chapter<Id>>,
+	#[html(select = ".latest", nested)]
+	latest: Option<Chapter<Id>>,
+	#[html(outer_html)]
+	source: String,
+	#[html(skip)]
+	key: String,
+}
+
+fn assert_from_element<T: FromElement>() {}
+
+#[test]
+fn test_derive_from_element() {
+	assert_from_element::<Chapter<u32>>();
+	assert_from_element::<Manga<u32>>();
+}
@@ -0,0 +1,111 @@
+#![cfg(all(feature = "json", feature = "imports"))]
+use aidoku::{
+	AidokuError,
+	helpers::graphql::{Query, parse_response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+#[derive(Serialize)]
+struct Variables {
+	id: i32,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Manga {
+	title: String,
+}
+
+fn body<V: Serialize>(query: &Query<V>, include_query: bool) -> Value {
+	serde_json::from_slice(&query.body(include_query).unwrap()).unwrap()
+}
+
+#[test]
+fn test_query_body() {
+	let query = Query::new("query ($id: Int) { manga(id: $id) { title } }")
+		.variables(Variables { id: 1 })
+		.operation_name("Manga");
+	assert_eq!(
+		body(&query, false),
+		json!({
+			"query": "query ($id: Int) { manga(id: $id) { title } }",
+			"operationName": "Manga",
+			"variables": { "id": 1 },
+		})
+	);
+	assert_eq!(
+		body(&Query::new("{ a }"), false),
+		json!({ "query": "{ a }" })
+	);
+}
+
+#[test]
+fn test_persisted_query_body() {
+	let extensions = json!({ "persistedQuery": { "version": 1, "sha256Hash": "abc" } });
+	let query = Query::persisted("abc").variables(Variables { id: 2 });
+	assert_eq!(
+		body(&query, false),
+		json!({ "variables": { "id": 2 }, "extensions": extensions })
+	);
+
+	// the document is only sent when the server doesn't know the hash
+	let query = Query::new("{ a }").persisted_hash("abc");
+	assert_eq!(body(&query, false), json!({ "extensions": extensions }));
+	assert_eq!(
+		body(&query, true),
+		json!({ "query": "{ a }", "extensions": extensions })
+	);
+}
+
+#[test]
+fn test_parse_response() {
+	let manga: Manga = parse_response(br#"{ "data": { "title": "A" } }"#).unwrap();
+	assert_eq!(manga.title, "A");
+
+	let result = parse_response::<Manga>(
+		br#"{
+			"data": null,
+			"errors": [
+				{ "message": "Not found", "path": ["manga"] },
+				{ "message": "Rate limited", "extensions": { "code": "RATE_LIMITED" } }
+			]
+		}"#,
+	);
+	assert!(matches!(
+		result,
+		Err(AidokuError::GraphqlError(messages)) if messages == ["Not found", "Rate limited"]
+	));
+
+	assert!(matches!(
+		parse_response::<Manga>(b"{}"),
+		Err(AidokuError::RequestError(_))
+	));
+	assert!(matches!(
+		parse_response::<Manga>(b"<html>"),
+		Err(AidokuError::JsonParseError(_))
+	));
+}
+
+#[test]
+fn test_should_resend() {
+	let query = Query::new("query { manga { title } }").persisted_hash("abc");
+	assert!(query.should_resend(br#"{ "errors": [{ "message": "PersistedQueryNotFound" }] }"#));
+	assert!(query.should_resend(
+		br#"{
+			"errors": [{
+				"message": "Persisted query not found",
+				"extensions": { "code": "PERSISTED_QUERY_NOT_FOUND" }
+			}]
+		}"#
+	));
+	assert!(!query.should_resend(
+		br#"{ "errors": [{ "message": "Rate limited", "extensions": { "code": "RATE_LIMITED" } }] }"#
+	));
+	assert!(!query.should_resend(br#"{ "data": { "title": "A" } }"#));
+	assert!(!query.should_resend(b"<html>"));
+
+	// queries without a document or a hash can't be sent again
+	let not_found = br#"{ "errors": [{ "message": "PersistedQueryNotFound" }] }"#;
+	assert!(!Query::persisted("abc").should_resend(not_found));
+	assert!(!Query::new("query { manga { title } }").should_resend(not_found));
+}
@@ -0,0 +1,234 @@
+#![cfg(feature = "imports")]
+use aidoku::imports::net::{
+	ContentRange, Cookie, Headers, MultipartForm, RequestError, RetryPolicy, is_challenge,
+	resolve_url,
+};
+
+#[test]
+fn test_should_retry() {
+	let policy = RetryPolicy::new(3);
+	assert!(policy.should_retry(1, Ok(429)));
+	assert!(policy.should_retry(2, Ok(503)));
+	assert!(!policy.should_retry(3, Ok(503)));
+	assert!(!policy.should_retry(1, Ok(200)));
+	assert!(!policy.should_retry(1, Ok(404)));
+	assert!(policy.should_retry(1, Err(RequestError::RequestError)));
+	assert!(!policy.should_retry(1, Err(RequestError::InvalidUrl)));
+
+	let policy = RetryPolicy::new(3).status_codes([500]).retry_errors(false);
+	assert!(policy.should_retry(1, Ok(500)));
+	assert!(!policy.should_retry(1, Ok(429)));
+	assert!(!policy.should_retry(1, Err(RequestError::RequestError)));
+
+	assert!(!RetryPolicy::new(1).should_retry(1, Ok(429)));
+}
+
+#[test]
+fn test_retry_delay() {
+	let policy = RetryPolicy::default();
+	assert_eq!(policy.retry_delay(1, None), Some(1));
+	assert_eq!(policy.retry_delay(2, None), Some(2));
+	assert_eq!(policy.retry_delay(3, None), Some(4));
+	assert_eq!(policy.retry_delay(10, None), Some(30));
+	assert_eq!(policy.retry_delay(1, Some(5)), Some(5));
+	assert_eq!(policy.retry_delay(1, Some(120)), None);
+
+	let policy = RetryPolicy::new(5).delay(3).backoff(1).max_delay(60);
+	assert_eq!(policy.retry_delay(4, None), Some(3));
+	assert_eq!(policy.retry_delay(1, Some(0)), Some(0));
+	assert_eq!(policy.retry_delay(1, Some(60)), Some(60));
+	assert_eq!(RetryPolicy::new(5).retry_delay(u32::MAX, None), Some(30));
+}
+
+#[test]
+fn test_resolve_url() {
+	let base = "https://example.com/api/v1/?page=1";
+	assert_eq!(
+		resolve_url(base, "https://other.com/a"),
+		"https://other.com/a"
+	);
+	assert_eq!(resolve_url(base, "data:text/plain,a"), "data:text/plain,a");
+	assert_eq!(
+		resolve_url(base, "//cdn.example.com/a.png"),
+		"https://cdn.example.com/a.png"
+	);
+	assert_eq!(resolve_url(base, "/search"), "https://example.com/search");
+	assert_eq!(
+		resolve_url(base, "search?q=a"),
+		"https://example.com/api/v1/search?q=a"
+	);
+	assert_eq!(
+		resolve_url(base, "?page=2"),
+		"https://example.com/api/v1/?page=2"
+	);
+	assert_eq!(resolve_url(base, ""), "https://example.com/api/v1/?page=1");
+
+	assert_eq!(
+		resolve_url("https://example.com", "manga/1"),
+		"https://example.com/manga/1"
+	);
+	assert_eq!(
+		resolve_url("https://example.com/manga/1", "2"),
+		"https://example.com/manga/2"
+	);
+
+	// dot segments are removed
+	assert_eq!(resolve_url("https://a.com/x/y", "../b"), "https://a.com/b");
+	assert_eq!(resolve_url("https://a.com/x/y", "./b"), "https://a.com/x/b");
+	assert_eq!(
+		resolve_url("https://a.com/x/y/z", "../.."),
+		"https://a.com/"
+	);
+	assert_eq!(resolve_url("https://a.com/x/y", "."), "https://a.com/x/");
+	assert_eq!(resolve_url("https://a.com/x", "../../b"), "https://a.com/b");
+	assert_eq!(
+		resolve_url("https://a.com/x/", "/a/./b/../c?d=../e"),
+		"https://a.com/a/c?d=../e"
+	);
+	assert_eq!(
+		resolve_url("https://a.com/", "//cdn.a.com/x/../y.png"),
+		"https://cdn.a.com/y.png"
+	);
+	assert_eq!(resolve_url("https://a.com/", "a//b"), "https://a.com/a//b");
+}
+
+#[test]
+fn test_multipart_form() {
+	let form = MultipartForm::new().text("title", "Chapter \"1\"").file(
+		"image",
+		"page.png",
+		"image/png",
+		b"\x89PNG".to_vec(),
+	);
+	let (content_type, data) = form.build();
+	assert_eq!(
+		content_type,
+		"multipart/form-data; boundary=AidokuFormBoundary0000000000000000"
+	);
+	assert_eq!(
+		data,
+		b"--AidokuFormBoundary0000000000000000\r\n\
+		Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+		Chapter \"1\"\r\n\
+		--AidokuFormBoundary0000000000000000\r\n\
+		Content-Disposition: form-data; name=\"image\"; filename=\"page.png\"\r\n\
+		Content-Type: image/png\r\n\r\n\
+		\x89PNG\r\n\
+		--AidokuFormBoundary0000000000000000--\r\n"
+	);
+
+	// the boundary can't occur in the data
+	let form = MultipartForm::new().text("a", "AidokuFormBoundary0000000000000000");
+	let (content_type, _) = form.build();
+	assert!(content_type.ends_with("AidokuFormBoundary0000000000000001"));
+}
+
+#[test]
+fn test_headers() {
+	let headers = Headers::new(vec![
+		("content-type".into(), "text/html".into()),
+		("set-cookie".into(), "a=1".into()),
+		(
+			"Set-Cookie".into(),
+			"b=2; Max-Age=60; Secure; HttpOnly".into(),
+		),
+	]);
+	assert_eq!(headers.len(), 3);
+	assert_eq!(headers.get("Content-Type"), Some("text/html"));
+	assert_eq!(headers.get("location"), None);
+	assert!(headers.contains("SET-COOKIE"));
+	assert_eq!(
+		headers.get_all("set-cookie").collect::<Vec<_>>(),
+		["a=1", "b=2; Max-Age=60; Secure; HttpOnly"]
+	);
+
+	let cookies = headers.cookies("example.com", 1000);
+	assert_eq!(cookies.len(), 2);
+	assert_eq!(cookies[0].name, "a");
+	assert_eq!(cookies[0].expires_date, None);
+	assert_eq!(cookies[1].name, "b");
+	assert_eq!(cookies[1].expires_date, Some(1060));
+	assert!(cookies[1].is_secure && cookies[1].is_http_only);
+}
+
+#[test]
+fn test_parse_cookie() {
+	assert_eq!(
+		Cookie::parse(
+			"session=abc=; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Domain=.Example.com; Path=/api",
+			"www.example.com",
+			0
+		),
+		Some(Cookie {
+			name: "session".into(),
+			value: "abc=".into(),
+			expires_date: Some(1445412480),
+			domain: "example.com".into(),
+			path: "/api".into(),
+			is_secure: false,
+			is_http_only: false,
+		})
+	);
+
+	let cookie = Cookie::parse(
+		"csrf=\"token\"; expires=Wed, 21-Oct-15 07:28:00 GMT",
+		"a.com",
+		0,
+	);
+	let cookie = cookie.unwrap();
+	assert_eq!(cookie.value, "token");
+	assert_eq!(cookie.domain, "a.com");
+	assert_eq!(cookie.path, "/");
+	assert_eq!(cookie.expires_date, Some(1445412480));
+
+	// max-age takes precedence over expires
+	let cookie = Cookie::parse(
+		"a=b; Max-Age=0; Expires=Wed, 21 Oct 2015 07:28:00 GMT",
+		"a.com",
+		5,
+	);
+	assert_eq!(cookie.unwrap().expires_date, Some(5));
+
+	assert_eq!(Cookie::parse("invalid", "a.com", 0), None);
+	assert_eq!(Cookie::parse("=value", "a.com", 0), None);
+	assert_eq!(Cookie::parse("a=b; Domain=other.com", "a.com", 0), None);
+}
+
+#[test]
+fn test_is_challenge() {
+	let cloudflare = b"<!DOCTYPE html><html><head><title>Just a moment...</title></head>\
+		<body><script src=\"/cdn-cgi/challenge-platform/h/b/orchestrate/chl_page/v1\"></script></body></html>";
+	assert!(is_challenge(403, Some("cloudflare"), None, cloudflare));
+	assert!(is_challenge(503, Some("cloudflare"), None, cloudflare));
+	assert!(!is_challenge(200, Some("cloudflare"), None, cloudflare));
+	assert!(!is_challenge(403, Some("nginx"), None, cloudflare));
+	assert!(!is_challenge(
+		403,
+		Some("cloudflare"),
+		None,
+		b"<title>Forbidden</title>"
+	));
+	assert!(is_challenge(403, None, Some("challenge"), b""));
+
+	let ddos_guard = b"<html><head><title>DDoS-Guard</title></head></html>";
+	assert!(is_challenge(403, Some("ddos-guard"), None, ddos_guard));
+}
+
+#[test]
+fn test_content_range() {
+	let range = ContentRange::parse("bytes 0-99/1234").unwrap();
+	assert_eq!(
+		range,
+		ContentRange {
+			start: 0,
+			end: 99,
+			total: Some(1234)
+		}
+	);
+	assert_eq!(range.len(), 100);
+	assert!(!range.is_empty());
+
+	assert_eq!(ContentRange::parse("bytes 100-199/*").unwrap().total, None);
+	assert_eq!(ContentRange::parse("bytes */1234"), None);
+	assert_eq!(ContentRange::parse("items 0-1/2"), None);
+}
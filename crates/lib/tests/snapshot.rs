@@ -0,0 +1,37 @@
+#![cfg(feature = "test")]
+use aidoku::imports::snapshot::{REDACTED, redact};
+use serde_json::json;
+
+#[test]
+fn test_redact() {
+	let mut value = json!({
+		"entries": [
+			{ "key": "a", "date": 1 },
+			{ "key": "b", "date": null },
+		],
+		"chapters": [{ "date": 2 }, { "date": 3 }],
+	});
+	redact(&mut value, ".entries[].date");
+	redact(&mut value, ".chapters[1].date");
+	redact(&mut value, ".missing[].date");
+	assert_eq!(
+		value,
+		json!({
+			"entries": [
+				{ "key": "a", "date": REDACTED },
+				{ "key": "b", "date": null },
+			],
+			"chapters": [{ "date": 2 }, { "date": REDACTED }],
+		})
+	);
+}
+
+#[test]
+fn test_redact_nested_arrays() {
+	let mut value = json!([[{ "a": 1 }], [{ "a": 2 }, { "b": 3 }]]);
+	redact(&mut value, "[][*].a");
+	assert_eq!(
+		value,
+		json!([[{ "a": REDACTED }], [{ "a": REDACTED }, { "b": 3 }]])
+	);
+}
@@ -1,11 +1,126 @@
 #![doc = include_str!("../README.md")]
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, parse_quote};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use std::path::{Path, PathBuf};
+use syn::{
+	Expr, Ident, LitStr, Token,
+	parse::{Parse, ParseStream},
+	parse_macro_input, parse_quote,
+	punctuated::Punctuated,
+	spanned::Spanned,
+};
 
 #[proc_macro_attribute]
-pub fn aidoku_test(_attr: TokenStream, item: TokenStream) -> TokenStream {
-	let mut item = parse_macro_input!(item as syn::ItemFn);
+pub fn aidoku_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+	let args = parse_macro_input!(attr as TestArgs);
+	let item = parse_macro_input!(item as syn::ItemFn);
+	let root = std::env::var("CARGO_MANIFEST_DIR")
+		.map(PathBuf::from)
+		.unwrap_or_default();
+	match expand_test(args, item, &root) {
+		Ok(tokens) => tokens.into(),
+		Err(err) => err.to_compile_error().into(),
+	}
+}
+
+/// Expands a test function, with fixture patterns relative to the given crate root.
+fn expand_test(args: TestArgs, mut item: syn::ItemFn, root: &Path) -> syn::Result<TokenStream2> {
+	// if the function should be ignored, add it to the export name
+	let mut ignore = "";
+	if let Some(i) = item.attrs.iter().position(is_ignore) {
+		item.attrs.remove(i);
+		ignore = "ignore$"
+	}
+
+	let (cases, span) = match args {
+		TestArgs::None => return Ok(single_test(item, ignore)),
+		TestArgs::Cases(cases, span) => (cases, span),
+		TestArgs::Fixtures(pattern) => (fixture_cases(&item, &pattern, root)?, pattern.span()),
+	};
+
+	let fn_name = &item.sig.ident;
+	let name = fn_name.to_string();
+	let param_count = item.sig.inputs.len();
+
+	let mut names = Vec::new();
+	let mut wrappers = Vec::new();
+	for (idx, case) in cases.into_iter().enumerate() {
+		let case_name = case.name.unwrap_or_else(|| format!("case_{}", idx + 1));
+		// cases are exported by name, so duplicates would fail to link
+		if names.contains(&case_name) {
+			return Err(syn::Error::new(
+				span,
+				format!("multiple test cases are named `{case_name}`"),
+			));
+		}
+		names.push(case_name.clone());
+		let args = case_args(case.value, param_count)?;
+		let wrapper = format_ident!("__aidoku_test_{}_{}", fn_name, idx);
+		wrappers.push(quote! {
+			#[cfg(test)]
+			#[unsafe(export_name = concat!("$aidoku-test$", #ignore, module_path!(), "::", #name, "::", #case_name))]
+			fn #wrapper() {
+				extern crate std;
+				std::panic::set_hook(::aidoku::alloc::Box::new(|info| {
+					::aidoku::prelude::println!("{info}");
+				}));
+				#fn_name(#(#args),*);
+			}
+		});
+	}
+
+	Ok(quote! {
+		#[cfg(test)]
+		#item
+
+		#(#wrappers)*
+	})
+}
+
+/// Exposes a benchmark to the test runner.
+///
+/// The function is called repeatedly, and its return value is passed through
+/// [`core::hint::black_box`] so that the work isn't optimized away.
+#[proc_macro_attribute]
+pub fn aidoku_bench(attr: TokenStream, item: TokenStream) -> TokenStream {
+	parse_macro_input!(attr as syn::parse::Nothing);
+	let item = parse_macro_input!(item as syn::ItemFn);
+	if !item.sig.inputs.is_empty() {
+		return syn::Error::new(
+			item.sig.inputs.span(),
+			"benchmark functions must not take any parameters",
+		)
+		.to_compile_error()
+		.into();
+	}
+
+	let fn_name = &item.sig.ident;
+	let name = fn_name.to_string();
+	let wrapper = format_ident!("__aidoku_bench_{}", fn_name);
+
+	// the runner calls the wrapper with the number of iterations to run, so the
+	// call overhead is only paid once per batch
+	let res = quote! {
+		#[cfg(test)]
+		#item
+
+		#[cfg(test)]
+		#[unsafe(export_name = concat!("$aidoku-bench$", module_path!(), "::", #name))]
+		fn #wrapper(iterations: u32) {
+			extern crate std;
+			std::panic::set_hook(::aidoku::alloc::Box::new(|info| {
+				::aidoku::prelude::println!("{info}");
+			}));
+			for _ in 0..iterations {
+				::core::hint::black_box(#fn_name());
+			}
+		}
+	};
+	res.into()
+}
+
+fn single_test(mut item: syn::ItemFn, ignore: &str) -> TokenStream2 {
 	let name = item.sig.ident.to_string();
 
 	// inject panic hook at the top of the function body
@@ -22,22 +137,303 @@ pub fn aidoku_test(_attr: TokenStream, item: TokenStream) -> TokenStream {
 		},
 	);
 
-	// if the function should be ignored, add it to the export name
-	let mut ignore = "";
-	if let Some(i) = item.attrs.iter().position(is_ignore) {
-		item.attrs.remove(i);
-		ignore = "ignore$"
-	}
-
 	// create a custom export name so we can read the exports in the test runner
-	let res = quote! {
+	quote! {
 		#[cfg(test)]
 		#[unsafe(export_name = concat!("$aidoku-test$", #ignore, module_path!(), "::",  #name))]
 		#item
-	};
-	res.into()
+	}
 }
 
 fn is_ignore(attr: &syn::Attribute) -> bool {
 	attr.path().is_ident("ignore")
 }
+
+/// The arguments given to the `aidoku_test` attribute.
+enum TestArgs {
+	None,
+	/// `cases(a, name = b, ...)`, with the span of the `cases` identifier
+	Cases(Vec<TestCase>, Span),
+	/// `fixtures = "path/to/*.html"`
+	Fixtures(LitStr),
+}
+
+struct TestCase {
+	name: Option<String>,
+	value: Expr,
+}
+
+impl Parse for TestArgs {
+	fn parse(input: ParseStream) -> syn::Result<Self> {
+		if input.is_empty() {
+			return Ok(Self::None);
+		}
+		let ident: Ident = input.parse()?;
+		let args = if ident == "cases" {
+			let content;
+			syn::parenthesized!(content in input);
+			let cases = Punctuated::<TestCase, Token![,]>::parse_terminated(&content)?;
+			Self::Cases(cases.into_iter().collect(), ident.span())
+		} else if ident == "fixtures" {
+			input.parse::<Token![=]>()?;
+			Self::Fixtures(input.parse()?)
+		} else {
+			return Err(syn::Error::new(
+				ident.span(),
+				"expected `cases(...)` or `fixtures = \"...\"`",
+			));
+		};
+		if !input.is_empty() {
+			return Err(input.error("unexpected tokens after test arguments"));
+		}
+		Ok(args)
+	}
+}
+
+impl Parse for TestCase {
+	fn parse(input: ParseStream) -> syn::Result<Self> {
+		// a case can optionally be named with `name = value`
+		let name = if input.peek(Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
+			let ident: Ident = input.parse()?;
+			input.parse::<Token![=]>()?;
+			Some(ident.to_string())
+		} else {
+			None
+		};
+		Ok(Self {
+			name,
+			value: input.parse()?,
+		})
+	}
+}
+
+/// Converts a case value into the arguments for the test function.
+///
+/// Tuples are spread across the parameters if the function takes more than one.
+fn case_args(value: Expr, param_count: usize) -> syn::Result<Vec<Expr>> {
+	match param_count {
+		0 => Err(syn::Error::new(
+			value.span(),
+			"test functions with cases must take at least one parameter",
+		)),
+		1 => Ok(vec![value]),
+		_ => match value {
+			Expr::Tuple(tuple) if tuple.elems.len() == param_count => {
+				Ok(tuple.elems.into_iter().collect())
+			}
+			value => Err(syn::Error::new(
+				value.span(),
+				format!("expected a tuple of {param_count} arguments"),
+			)),
+		},
+	}
+}
+
+/// Creates a test case for each file matching the given fixture pattern.
+///
+/// The pattern is relative to the crate root, and the last path component may contain
+/// a single `*` wildcard. If the pattern is a directory, every file inside it is used.
+/// Cases are named after the file stem, so files that only differ in their extension
+/// are an error.
+fn fixture_cases(item: &syn::ItemFn, pattern: &LitStr, root: &Path) -> syn::Result<Vec<TestCase>> {
+	if item.sig.inputs.len() != 1 {
+		return Err(syn::Error::new(
+			item.sig.inputs.span(),
+			"test functions with fixtures must take exactly one parameter",
+		));
+	}
+	let bytes = item.sig.inputs.first().is_some_and(is_byte_slice);
+
+	let files = match_fixtures(root, &pattern.value())
+		.map_err(|err| syn::Error::new(pattern.span(), err))?;
+
+	Ok(files
+		.into_iter()
+		.map(|file| {
+			let name = file
+				.file_stem()
+				.map(|stem| stem.to_string_lossy().into_owned());
+			let path = file.to_string_lossy().into_owned();
+			let value = if bytes {
+				parse_quote! { include_bytes!(#path) }
+			} else {
+				parse_quote! { include_str!(#path) }
+			};
+			TestCase { name, value }
+		})
+		.collect())
+}
+
+fn match_fixtures(root: &Path, pattern: &str) -> Result<Vec<PathBuf>, String> {
+	let full = root.join(pattern);
+	let (dir, file_pattern) = if full.is_dir() {
+		(full, None)
+	} else {
+		let file_pattern = full
+			.file_name()
+			.map(|name| name.to_string_lossy().into_owned());
+		let dir = full.parent().map(Path::to_path_buf).unwrap_or_default();
+		(dir, file_pattern)
+	};
+
+	let entries = std::fs::read_dir(&dir).map_err(|err| {
+		format!(
+			"failed to read fixture directory `{}`: {err}",
+			dir.display()
+		)
+	})?;
+	let mut files = entries
+		.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		.filter(|path| path.is_file())
+		.filter(|path| {
+			let Some(file_pattern) = file_pattern.as_ref() else {
+				return true;
+			};
+			let name = path
+				.file_name()
+				.map(|name| name.to_string_lossy())
+				.unwrap_or_default();
+			match file_pattern.split_once('*') {
+				Some((prefix, suffix)) => {
+					name.len() >= prefix.len() + suffix.len()
+						&& name.starts_with(prefix)
+						&& name.ends_with(suffix)
+				}
+				None => name == file_pattern.as_str(),
+			}
+		})
+		.collect::<Vec<_>>();
+	if files.is_empty() {
+		return Err(format!("no fixture files match `{pattern}`"));
+	}
+	files.sort();
+	Ok(files)
+}
+
+fn is_byte_slice(arg: &syn::FnArg) -> bool {
+	let syn::FnArg::Typed(arg) = arg else {
+		return false;
+	};
+	let syn::Type::Reference(reference) = arg.ty.as_ref() else {
+		return false;
+	};
+	let syn::Type::Slice(slice) = reference.elem.as_ref() else {
+		return false;
+	};
+	matches!(slice.elem.as_ref(), syn::Type::Path(path) if path.path.is_ident("u8"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn expand(args: &str, item: &str, root: &Path) -> syn::Result<String> {
+		let args = syn::parse_str::<TestArgs>(args)?;
+		let item = syn::parse_str::<syn::ItemFn>(item)?;
+		expand_test(args, item, root).map(|tokens| tokens.to_string())
+	}
+
+	/// Creates a directory of fixture files in the temporary directory.
+	fn fixtures(name: &str, files: &[&str]) -> PathBuf {
+		let root = std::env::temp_dir().join(format!("aidoku-test-{name}-{}", std::process::id()));
+		let dir = root.join("fixtures");
+		std::fs::create_dir_all(&dir).unwrap();
+		for file in files {
+			std::fs::write(dir.join(file), "").unwrap();
+		}
+		root
+	}
+
+	#[test]
+	fn test_case_names() {
+		let output = expand(
+			"cases(1, named = 2, 3)",
+			"fn test_value(value: i32) {}",
+			Path::new(""),
+		)
+		.unwrap();
+		for name in ["\"case_1\"", "\"named\"", "\"case_3\""] {
+			assert!(output.contains(name), "missing {name} in {output}");
+		}
+
+		let err = expand(
+			"cases(a = 1, a = 2)",
+			"fn test_value(value: i32) {}",
+			Path::new(""),
+		)
+		.unwrap_err();
+		assert_eq!(err.to_string(), "multiple test cases are named `a`");
+
+		let err = expand("cases(1)", "fn test_value() {}", Path::new("")).unwrap_err();
+		assert!(err.to_string().contains("at least one parameter"));
+	}
+
+	#[test]
+	fn test_ignore() {
+		let output = expand("", "#[ignore] fn test_value() {}", Path::new("")).unwrap();
+		assert!(output.contains("\"ignore$\""));
+		assert!(!output.contains("# [ignore]"));
+
+		let output = expand(
+			"cases(1)",
+			"#[ignore] fn test_value(value: i32) {}",
+			Path::new(""),
+		)
+		.unwrap();
+		assert!(output.contains("\"ignore$\""));
+		assert!(!output.contains("# [ignore]"));
+
+		let output = expand("", "fn test_value() {}", Path::new("")).unwrap();
+		assert!(!output.contains("ignore$"));
+	}
+
+	#[test]
+	fn test_fixtures() {
+		let root = fixtures("fixtures", &["a.html", "b.html", "c.json"]);
+
+		let output = expand(
+			"fixtures = \"fixtures/*.html\"",
+			"fn test_page(html: &str) {}",
+			&root,
+		)
+		.unwrap();
+		assert!(output.contains("\"a\"") && output.contains("\"b\""));
+		assert!(!output.contains("\"c\""));
+		assert!(output.contains("include_str !"));
+		assert!(!output.contains("include_bytes !"));
+
+		// byte slice parameters get the file's bytes
+		let output = expand(
+			"fixtures = \"fixtures\"",
+			"fn test_page(data: &[u8]) {}",
+			&root,
+		)
+		.unwrap();
+		assert!(output.contains("\"c\""));
+		assert!(output.contains("include_bytes !"));
+		assert!(!output.contains("include_str !"));
+
+		let err = expand(
+			"fixtures = \"fixtures/*.png\"",
+			"fn test_page(data: &[u8]) {}",
+			&root,
+		)
+		.unwrap_err();
+		assert_eq!(err.to_string(), "no fixture files match `fixtures/*.png`");
+
+		std::fs::remove_dir_all(&root).unwrap();
+	}
+
+	#[test]
+	fn test_fixture_name_collision() {
+		let root = fixtures("collision", &["page.html", "page.json"]);
+		let err = expand(
+			"fixtures = \"fixtures/page.*\"",
+			"fn test_page(data: &str) {}",
+			&root,
+		)
+		.unwrap_err();
+		assert_eq!(err.to_string(), "multiple test cases are named `page`");
+		std::fs::remove_dir_all(&root).unwrap();
+	}
+}
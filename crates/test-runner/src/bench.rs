@@ -0,0 +1,140 @@
+//! Benchmarking of functions exported with `#[aidoku_bench]`.
+//!
+//! Each benchmark is run in batches of iterations, with the batch size chosen so that a single
+//! batch takes long enough to be timed accurately. Results can be saved as a baseline and
+//! compared against in later runs.
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	fmt,
+	path::Path,
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+/// Environment variable containing the path that benchmark results should be saved to.
+pub const BENCH_SAVE_VAR: &str = "AIDOKU_BENCH_SAVE";
+/// Environment variable containing the path of saved results to compare against.
+pub const BENCH_BASELINE_VAR: &str = "AIDOKU_BENCH_BASELINE";
+
+/// The number of timed batches for each benchmark.
+const SAMPLES: u32 = 50;
+/// The minimum duration of a single batch.
+const MIN_BATCH_TIME: Duration = Duration::from_millis(2);
+/// The maximum number of iterations in a single batch.
+const MAX_BATCH_SIZE: u32 = 1 << 24;
+
+/// The measured time of a benchmark.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BenchResult {
+	/// The total number of timed iterations.
+	pub iterations: u64,
+	/// The mean time of an iteration, in nanoseconds.
+	pub mean: f64,
+	/// The variance of the iteration time between batches, in nanoseconds squared.
+	pub variance: f64,
+}
+
+impl BenchResult {
+	pub fn std_dev(&self) -> f64 {
+		self.variance.sqrt()
+	}
+}
+
+/// Measures the time of a benchmark.
+///
+/// The given function runs the benchmark for the given number of iterations.
+pub fn measure(mut run: impl FnMut(u32) -> Result<()>) -> Result<BenchResult> {
+	// double the batch size until a batch is long enough, which also warms up the benchmark
+	let mut batch_size = 1;
+	loop {
+		let start = Instant::now();
+		run(batch_size)?;
+		if start.elapsed() >= MIN_BATCH_TIME || batch_size >= MAX_BATCH_SIZE {
+			break;
+		}
+		batch_size *= 2;
+	}
+
+	let mut samples = Vec::with_capacity(SAMPLES as usize);
+	for _ in 0..SAMPLES {
+		let start = Instant::now();
+		run(batch_size)?;
+		samples.push(start.elapsed().as_nanos() as f64 / batch_size as f64);
+	}
+	let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+	let variance = samples
+		.iter()
+		.map(|sample| (sample - mean).powi(2))
+		.sum::<f64>()
+		/ (samples.len() - 1) as f64;
+	Ok(BenchResult {
+		iterations: batch_size as u64 * SAMPLES as u64,
+		mean,
+		variance,
+	})
+}
+
+/// Results of all benchmarks that were run, keyed by name.
+#[derive(Debug, Default)]
+pub struct Benchmarks {
+	results: Mutex<BTreeMap<String, BenchResult>>,
+	baseline: BTreeMap<String, BenchResult>,
+}
+
+impl Benchmarks {
+	/// Creates an empty set of results, compared against the given baseline.
+	pub fn new(baseline: BTreeMap<String, BenchResult>) -> Self {
+		Self {
+			results: Mutex::default(),
+			baseline,
+		}
+	}
+
+	/// Loads saved results to use as the baseline.
+	pub fn with_baseline(path: &Path) -> Result<Self> {
+		let data = std::fs::read(path)
+			.with_context(|| format!("Failed to read baseline {}", path.display()))?;
+		let baseline = serde_json::from_slice(&data)
+			.with_context(|| format!("Failed to parse baseline {}", path.display()))?;
+		Ok(Self::new(baseline))
+	}
+
+	pub fn insert(&self, name: impl Into<String>, result: BenchResult) {
+		self.results.lock().unwrap().insert(name.into(), result);
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.results.lock().unwrap().is_empty()
+	}
+
+	/// Writes the results to a file, to be used as a baseline later.
+	pub fn save(&self, path: &Path) -> Result<()> {
+		let results = self.results.lock().unwrap();
+		let data = serde_json::to_string_pretty(&*results)?;
+		std::fs::write(path, data)
+			.with_context(|| format!("Failed to write benchmark results to {}", path.display()))
+	}
+}
+
+impl fmt::Display for Benchmarks {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let results = self.results.lock().unwrap();
+		let width = results.keys().map(|name| name.len()).max().unwrap_or(0);
+		for (name, result) in results.iter() {
+			let mut line = format!(
+				"{name:<width$} {:>12} iterations {:>12} ± {:<12}",
+				result.iterations,
+				format!("{:.3?}", Duration::from_nanos(result.mean as u64)),
+				format!("{:.3?}", Duration::from_nanos(result.std_dev() as u64)),
+			);
+			if let Some(baseline) = self.baseline.get(name) {
+				let change = (result.mean - baseline.mean) / baseline.mean * 100.0;
+				line.push_str(&format!(" {change:+.2}% vs baseline"));
+			}
+			writeln!(f, "{}", line.trim_end())?;
+		}
+		Ok(())
+	}
+}
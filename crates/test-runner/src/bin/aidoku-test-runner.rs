@@ -1,7 +1,13 @@
-use aidoku_test_runner::{imports, libs};
-use anyhow::{Result, bail};
-use libtest_mimic::{Arguments, Failed, Trial};
-use std::process::ExitCode;
+use aidoku_test_runner::{
+	bench::{BENCH_BASELINE_VAR, BENCH_SAVE_VAR, Benchmarks, measure},
+	config::Config,
+	coverage::{COVERAGE_VAR, Coverage},
+	imports, libs,
+	profile::{PROFILE_VAR, profile_imports},
+};
+use anyhow::{Result, anyhow, bail};
+use libtest_mimic::{Arguments, Failed, Measurement, Trial};
+use std::{path::PathBuf, process::ExitCode, sync::Arc};
 use wasmer::*;
 
 use libs::WasmEnv;
@@ -16,9 +22,11 @@ fn main() -> Result<ExitCode> {
 
 	let args = Arguments::from_iter(std::env::args().skip(1)); // skip the test runner executable and use wasm file as executable
 
+	let config = Arc::new(Config::discover()?);
+
 	let mut store = Store::default();
 	let module = Module::from_file(&store, &file)?;
-	let env = FunctionEnv::new(&mut store, WasmEnv::new());
+	let env = FunctionEnv::new(&mut store, WasmEnv::with_config(config.clone())?);
 	let imports = imports::generate_imports(&mut store, &env);
 	let instance = Instance::new(&mut store, &module, &imports)?;
 	{
@@ -26,8 +34,36 @@ fn main() -> Result<ExitCode> {
 		env_mut.memory = Some(instance.exports.get_memory("memory")?.clone());
 	}
 
+	// instrument the module for coverage if an output path is given
+	let coverage_path = std::env::var_os(COVERAGE_VAR).map(PathBuf::from);
+	let coverage = match coverage_path {
+		Some(_) => {
+			let coverage = Coverage::new(&std::fs::read(&file)?)?;
+			if !coverage.has_line_info() {
+				eprintln!("warning: no line information found, build the source with debug info");
+			}
+			Some(Arc::new(coverage))
+		}
+		None => None,
+	};
+
+	let benchmarks = Arc::new(match std::env::var_os(BENCH_BASELINE_VAR) {
+		Some(path) => Benchmarks::with_baseline(path.as_ref())?,
+		None => Benchmarks::default(),
+	});
+
 	let mut tests = Vec::new();
 	for export in module.exports() {
+		if let Some(name) = export.name().strip_prefix("$aidoku-bench$") {
+			let file = file.clone();
+			let config = config.clone();
+			let benchmarks = benchmarks.clone();
+			let export_name = export.name().to_string();
+			tests.push(Trial::bench(name, move |test_mode| {
+				run_bench(&file, &export_name, &config, &benchmarks, test_mode)
+			}));
+			continue;
+		}
 		if let Some(name) = export
 			.name()
 			.strip_prefix("$aidoku-test$")
@@ -40,30 +76,71 @@ fn main() -> Result<ExitCode> {
 			});
 
 			let file = file.clone();
-			let trial = Trial::test(name, move || run_test(&file, export.name(), args.nocapture))
-				.with_ignored_flag(ignore);
+			let config = config.clone();
+			let coverage = coverage.clone();
+			let trial = Trial::test(name, move || {
+				run_test(
+					&file,
+					export.name(),
+					args.nocapture,
+					&config,
+					coverage.as_ref(),
+				)
+			})
+			.with_ignored_flag(ignore);
 			tests.push(trial);
 		}
 	}
 
-	libtest_mimic::run(&args, tests).exit();
+	let conclusion = libtest_mimic::run(&args, tests);
+	if let (Some(coverage), Some(path)) = (coverage, coverage_path) {
+		coverage.write_lcov(&path)?;
+	}
+	if !benchmarks.is_empty() {
+		print!("\nbenchmarks:\n{benchmarks}");
+		if let Some(path) = std::env::var_os(BENCH_SAVE_VAR) {
+			benchmarks.save(path.as_ref())?;
+		}
+	}
+	conclusion.exit();
 }
 
-fn run_test(file: &str, name: &str, nocapture: bool) -> Result<(), Failed> {
-	let mut store = Store::default();
+fn run_test(
+	file: &str,
+	name: &str,
+	nocapture: bool,
+	config: &Arc<Config>,
+	coverage: Option<&Arc<Coverage>>,
+) -> Result<(), Failed> {
+	let mut store = match coverage {
+		Some(coverage) => coverage.store(),
+		None => Store::default(),
+	};
 	let module = Module::from_file(&store, file)?;
-	let env = FunctionEnv::new(&mut store, WasmEnv::new());
-	let imports = imports::generate_imports(&mut store, &env);
+	let env = FunctionEnv::new(&mut store, WasmEnv::with_config(config.clone())?);
+	let mut imports = imports::generate_imports(&mut store, &env);
+	if std::env::var_os(PROFILE_VAR).is_some() {
+		imports = profile_imports(&mut store, &env, &imports);
+	}
 	let instance = Instance::new(&mut store, &module, &imports)?;
+	let test_name = name.trim_start_matches("$aidoku-test$");
+	let test_name = test_name.trim_start_matches("ignore$");
 	{
 		let env_mut = env.as_mut(&mut store);
 		env_mut.memory = Some(instance.exports.get_memory("memory")?.clone());
+		env_mut.test_name = Some(test_name.into());
 	}
 
 	let f = instance
 		.exports
 		.get_typed_function::<(), ()>(&store, name)?;
 	let result = f.call(&mut store);
+	if let Some(coverage) = coverage {
+		coverage.collect(&mut store, &instance);
+	}
+	if let Some(profile) = env.as_ref(&store).profile.as_ref() {
+		print!("\nprofile for {test_name}:\n{profile}");
+	}
 	match result {
 		Ok(_) => {
 			// print stdout if not capturing output
@@ -87,3 +164,46 @@ fn run_test(file: &str, name: &str, nocapture: bool) -> Result<(), Failed> {
 		}
 	}
 }
+
+fn run_bench(
+	file: &str,
+	name: &str,
+	config: &Arc<Config>,
+	benchmarks: &Benchmarks,
+	test_mode: bool,
+) -> Result<Option<Measurement>, Failed> {
+	let mut store = Store::default();
+	let module = Module::from_file(&store, file)?;
+	let env = FunctionEnv::new(&mut store, WasmEnv::with_config(config.clone())?);
+	let imports = imports::generate_imports(&mut store, &env);
+	let instance = Instance::new(&mut store, &module, &imports)?;
+	let bench_name = name.trim_start_matches("$aidoku-bench$");
+	{
+		let env_mut = env.as_mut(&mut store);
+		env_mut.memory = Some(instance.exports.get_memory("memory")?.clone());
+		env_mut.test_name = Some(bench_name.into());
+	}
+
+	let f = instance
+		.exports
+		.get_typed_function::<u32, ()>(&store, name)?;
+	let mut run = |iterations| {
+		let result = f.call(&mut store, iterations);
+		// clear stdout between batches, so that it doesn't grow with every iteration
+		let stdout = std::mem::take(&mut env.as_mut(&mut store).stdout);
+		result
+			.map(|_| ())
+			.map_err(|_| anyhow!("{}", stdout.strip_suffix("\n").unwrap_or(&stdout)))
+	};
+	if test_mode {
+		// only check that the benchmark runs when not benchmarking
+		run(1)?;
+		return Ok(None);
+	}
+	let result = measure(run)?;
+	benchmarks.insert(bench_name, result);
+	Ok(Some(Measurement {
+		avg: result.mean as u64,
+		variance: result.std_dev() as u64,
+	}))
+}
@@ -0,0 +1,291 @@
+//! Configuration of the test environment.
+//!
+//! The runner looks for an `aidoku-test.toml` file next to the `Cargo.toml` of the crate being
+//! tested. All fields are optional:
+//!
+//! ```toml
+//! [net]
+//! user_agent = "Mozilla/5.0"
+//! headers = { Referer = "https://example.com/" }
+//! timeout = 10.0 # seconds
+//! proxy = "http://localhost:8080"
+//! policy = "offline"
+//! allowed_hosts = ["api.example.com"]
+//!
+//! [defaults]
+//! languages = ["en"]
+//! showNsfw = true
+//!
+//! [clock]
+//! now = 2024-01-01T00:00:00Z
+//! timezone = "Asia/Tokyo"
+//!
+//! [dirs]
+//! fixtures = "tests/fixtures"
+//! snapshots = "tests/snapshots"
+//! fonts = "tests/fonts"
+//! ```
+use crate::libs::{DefaultValue, HttpMethod, UserDefaults};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer};
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+};
+use url::Url;
+
+/// The name of the configuration file.
+pub const CONFIG_FILE: &str = "aidoku-test.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+	/// The directory that relative paths are resolved against.
+	#[serde(skip)]
+	pub root: PathBuf,
+	pub net: NetConfig,
+	/// Initial values of the defaults store.
+	pub defaults: HashMap<String, toml::Value>,
+	pub clock: ClockConfig,
+	pub dirs: DirsConfig,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NetPolicy {
+	/// All requests are sent.
+	#[default]
+	Online,
+	/// Only requests to allowed hosts are sent.
+	Offline,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetConfig {
+	/// The user agent used for requests that don't set one.
+	pub user_agent: Option<String>,
+	/// Headers added to every request that doesn't set them.
+	pub headers: HashMap<String, String>,
+	/// The timeout in seconds used for requests that don't set one.
+	pub timeout: Option<f64>,
+	/// A proxy url that all requests are sent through.
+	pub proxy: Option<String>,
+	pub policy: NetPolicy,
+	/// Hosts that requests can still be sent to when offline.
+	pub allowed_hosts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClockConfig {
+	/// A fixed current date.
+	#[serde(deserialize_with = "deserialize_date")]
+	pub now: Option<DateTime<Utc>>,
+	/// The local timezone.
+	#[serde(deserialize_with = "deserialize_timezone")]
+	pub timezone: Option<Tz>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DirsConfig {
+	/// A directory of responses that are used instead of sending requests.
+	pub fixtures: Option<PathBuf>,
+	/// The directory that snapshots are stored in.
+	pub snapshots: Option<PathBuf>,
+	/// A directory of fonts that are used instead of fetching them.
+	pub fonts: Option<PathBuf>,
+}
+
+impl Config {
+	/// Parses a configuration file, resolving relative paths against the given root.
+	pub fn parse(contents: &str, root: impl Into<PathBuf>) -> Result<Self> {
+		let mut config: Self = toml::from_str(contents)?;
+		config.root = root.into();
+		if let Some(proxy) = &config.net.proxy {
+			reqwest::Proxy::all(proxy).with_context(|| format!("Invalid proxy `{proxy}`"))?;
+		}
+		config.user_defaults()?;
+		Ok(config)
+	}
+
+	/// Loads the configuration file of the crate being tested, if it exists.
+	///
+	/// The crate directory is taken from `CARGO_MANIFEST_DIR`, which cargo sets when
+	/// invoking the runner, or the current directory otherwise.
+	pub fn discover() -> Result<Self> {
+		let root = std::env::var_os("CARGO_MANIFEST_DIR")
+			.map(PathBuf::from)
+			.or_else(|| std::env::current_dir().ok())
+			.unwrap_or_default();
+		let path = root.join(CONFIG_FILE);
+		match std::fs::read_to_string(&path) {
+			Ok(contents) => Self::parse(&contents, root)
+				.with_context(|| format!("Failed to parse {}", path.display())),
+			Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self {
+				root,
+				..Default::default()
+			}),
+			Err(err) => Err(err).with_context(|| format!("Failed to read {}", path.display())),
+		}
+	}
+
+	fn resolve(&self, path: &Path) -> PathBuf {
+		self.root.join(path)
+	}
+
+	pub fn fixtures_dir(&self) -> Option<PathBuf> {
+		self.dirs.fixtures.as_deref().map(|dir| self.resolve(dir))
+	}
+
+	pub fn snapshots_dir(&self) -> PathBuf {
+		self.resolve(
+			self.dirs
+				.snapshots
+				.as_deref()
+				.unwrap_or(Path::new("snapshots")),
+		)
+	}
+
+	pub fn fonts_dir(&self) -> Option<PathBuf> {
+		self.dirs.fonts.as_deref().map(|dir| self.resolve(dir))
+	}
+
+	/// Returns the current date, which is fixed if configured.
+	pub fn now(&self) -> DateTime<Utc> {
+		self.clock.now.unwrap_or_else(Utc::now)
+	}
+
+	/// Returns the configured defaults as a defaults store.
+	pub fn user_defaults(&self) -> Result<UserDefaults> {
+		let mut defaults = UserDefaults::new();
+		for (key, value) in &self.defaults {
+			let Some(value) = default_value(value) else {
+				bail!("Unsupported value for default `{key}`");
+			};
+			defaults.set(key.clone(), value);
+		}
+		Ok(defaults)
+	}
+
+	/// Reads the fixture for a request, if one exists.
+	///
+	/// Fixtures are stored at `<host>/<path>`, with `index.html` used for paths ending in `/`.
+	/// For urls with a query, the fixture at `<host>/<path>?<query>` is used if it exists, with
+	/// the `?` and other characters that can't be in file names percent-encoded, like
+	/// `manga/1%3Fpage=2`. A `%` in the query is encoded as `%25`, so a query that was already
+	/// percent-encoded doesn't collide with a decoded one. Otherwise, the fixture without the
+	/// query is used.
+	///
+	/// Requests with methods other than `GET` and `HEAD` only use fixtures with the method as
+	/// an extension, like `api/graphql.post` or `manga/1%3Fpage=2.post`.
+	pub fn fixture(&self, method: HttpMethod, url: &Url) -> Option<Vec<u8>> {
+		let mut path = self.fixtures_dir()?;
+		path.push(url.host_str()?);
+		for segment in url.path_segments()? {
+			if segment == ".." {
+				return None;
+			}
+			path.push(segment);
+		}
+		if url.path().ends_with('/') {
+			path.push("index.html");
+		}
+		let extension = match method {
+			HttpMethod::Get | HttpMethod::Head => "",
+			HttpMethod::Post => ".post",
+			HttpMethod::Put => ".put",
+			HttpMethod::Delete => ".delete",
+			HttpMethod::Patch => ".patch",
+			HttpMethod::Options => ".options",
+			HttpMethod::Connect => ".connect",
+			HttpMethod::Trace => ".trace",
+		};
+		let query = url
+			.query()
+			.map(|query| format!("%3F{}{extension}", encode_file_name(query)));
+		query
+			.into_iter()
+			.chain(std::iter::once(extension.to_string()))
+			.find_map(|suffix| {
+				let mut file = path.clone().into_os_string();
+				file.push(suffix);
+				std::fs::read(file).ok()
+			})
+	}
+}
+
+/// Percent-encodes the characters of a url query that can't be in file names, and `%` itself.
+fn encode_file_name(value: &str) -> String {
+	let mut encoded = String::with_capacity(value.len());
+	for char in value.chars() {
+		match char {
+			'%' | '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => {
+				encoded.push_str(&format!("%{:02X}", char as u8));
+			}
+			char => encoded.push(char),
+		}
+	}
+	encoded
+}
+
+impl NetConfig {
+	/// Returns true if the network policy allows requests to the given url.
+	pub fn allows(&self, url: &Url) -> bool {
+		match self.policy {
+			NetPolicy::Online => true,
+			NetPolicy::Offline => url
+				.host_str()
+				.is_some_and(|host| self.allowed_hosts.iter().any(|allowed| allowed == host)),
+		}
+	}
+}
+
+fn default_value(value: &toml::Value) -> Option<DefaultValue> {
+	Some(match value {
+		toml::Value::String(value) => DefaultValue::String(value.clone()),
+		toml::Value::Integer(value) => DefaultValue::Int((*value).try_into().ok()?),
+		toml::Value::Float(value) => DefaultValue::Float(*value as f32),
+		toml::Value::Boolean(value) => DefaultValue::Bool(*value),
+		toml::Value::Array(values) => DefaultValue::StringArray(
+			values
+				.iter()
+				.map(|value| value.as_str().map(String::from))
+				.collect::<Option<_>>()?,
+		),
+		toml::Value::Table(table) => DefaultValue::HashMap(
+			table
+				.iter()
+				.map(|(key, value)| Some((key.clone(), value.as_str()?.into())))
+				.collect::<Option<_>>()?,
+		),
+		toml::Value::Datetime(_) => return None,
+	})
+}
+
+fn deserialize_date<'de, D: Deserializer<'de>>(
+	deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error> {
+	// accept both toml datetimes and strings
+	let date = match toml::Value::deserialize(deserializer)? {
+		toml::Value::Datetime(date) => date.to_string(),
+		toml::Value::String(date) => date,
+		_ => return Err(serde::de::Error::custom("expected a date")),
+	};
+	DateTime::parse_from_rfc3339(&date)
+		.map(|date| Some(date.to_utc()))
+		.map_err(|err| serde::de::Error::custom(format!("invalid date `{date}`: {err}")))
+}
+
+fn deserialize_timezone<'de, D: Deserializer<'de>>(
+	deserializer: D,
+) -> Result<Option<Tz>, D::Error> {
+	let timezone = String::deserialize(deserializer)?;
+	timezone
+		.parse()
+		.map(Some)
+		.map_err(|_| serde::de::Error::custom(format!("unknown timezone `{timezone}`")))
+}
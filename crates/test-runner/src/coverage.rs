@@ -0,0 +1,324 @@
+//! Code coverage for tests running in the runner.
+//!
+//! Every basic block in the module is instrumented with a counter (a mutable global) by a
+//! wasmer middleware. After each test, the counters are read back and accumulated, and the
+//! hits are mapped to source lines using the module's DWARF line tables to produce an lcov report.
+use anyhow::{Context, Result};
+use gimli::{EndianSlice, LittleEndian};
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt::Write,
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex, OnceLock},
+};
+use wasmer::{
+	ExportIndex, GlobalInit, GlobalType, Instance, LocalFunctionIndex, Mutability, Store, Type,
+	sys::{
+		CompilerConfig, Cranelift, EngineBuilder, FunctionMiddleware, MiddlewareError,
+		MiddlewareReaderState, ModuleMiddleware,
+		wasmparser::{Operator, Parser, Payload},
+	},
+};
+use wasmer_types::{GlobalIndex, ModuleInfo, entity::EntityRef};
+
+/// Environment variable containing the path that the lcov report should be written to.
+pub const COVERAGE_VAR: &str = "AIDOKU_TEST_COVERAGE";
+
+const COUNTER_PREFIX: &str = "__aidoku_cov_";
+
+/// Returns true if a new basic block begins after the given operator.
+fn ends_block(operator: &Operator) -> bool {
+	matches!(
+		operator,
+		Operator::Block { .. }
+			| Operator::Loop { .. }
+			| Operator::If { .. }
+			| Operator::Else
+			| Operator::End
+			| Operator::Br { .. }
+			| Operator::BrIf { .. }
+			| Operator::BrTable { .. }
+			| Operator::Return
+			| Operator::Unreachable
+	)
+}
+
+#[derive(Debug)]
+struct Function {
+	/// Code section offset of the start of the function body.
+	start: u64,
+	/// Code section offset of the end of the function body.
+	end: u64,
+	/// Code section offsets of the start of each basic block.
+	blocks: Vec<u64>,
+	/// Index of the first counter for this function.
+	first_counter: usize,
+}
+
+/// Coverage state for a single module, shared between all tests.
+#[derive(Debug)]
+pub struct Coverage {
+	functions: Vec<Function>,
+	counter_count: usize,
+	/// The global index of the first counter, set when the module is transformed.
+	first_global: OnceLock<u32>,
+	hits: Mutex<Vec<u64>>,
+	lines: BTreeMap<PathBuf, BTreeMap<u64, Vec<usize>>>,
+}
+
+impl Coverage {
+	/// Finds the basic blocks and source line mappings of the given module.
+	pub fn new(wasm: &[u8]) -> Result<Self> {
+		let mut functions = Vec::new();
+		let mut code_start = 0;
+		let mut sections: HashMap<&str, &[u8]> = HashMap::new();
+		let mut counter_count = 0;
+
+		for payload in Parser::new(0).parse_all(wasm) {
+			match payload? {
+				Payload::CodeSectionStart { range, .. } => code_start = range.start as u64,
+				Payload::CodeSectionEntry(body) => {
+					let range = body.range();
+					let mut blocks = Vec::new();
+					let mut new_block = true;
+					let mut reader = body.get_operators_reader()?;
+					while !reader.eof() {
+						let (operator, offset) = reader.read_with_offset()?;
+						if new_block {
+							blocks.push(offset as u64 - code_start);
+						}
+						new_block = ends_block(&operator);
+					}
+					functions.push(Function {
+						start: range.start as u64 - code_start,
+						end: range.end as u64 - code_start,
+						first_counter: counter_count,
+						blocks,
+					});
+					counter_count += functions.last().map(|f| f.blocks.len()).unwrap_or(0);
+				}
+				Payload::CustomSection(section) if section.name().starts_with(".debug_") => {
+					sections.insert(section.name(), section.data());
+				}
+				_ => {}
+			}
+		}
+
+		let mut coverage = Self {
+			functions,
+			counter_count,
+			first_global: OnceLock::new(),
+			hits: Mutex::new(vec![0; counter_count]),
+			lines: BTreeMap::new(),
+		};
+		coverage.lines = coverage.map_lines(&sections)?;
+		Ok(coverage)
+	}
+
+	/// Creates a store that compiles modules with coverage counters.
+	pub fn store(self: &Arc<Self>) -> Store {
+		let mut compiler = Cranelift::default();
+		compiler.push_middleware(self.clone());
+		Store::new(EngineBuilder::new(compiler))
+	}
+
+	/// Adds the counter values of an instance to the accumulated hits.
+	pub fn collect(&self, store: &mut Store, instance: &Instance) {
+		let mut hits = self.hits.lock().unwrap();
+		for (idx, hit) in hits.iter_mut().enumerate() {
+			let Ok(global) = instance
+				.exports
+				.get_global(&format!("{COUNTER_PREFIX}{idx}"))
+			else {
+				continue;
+			};
+			if let Some(value) = global.get(store).i32() {
+				*hit += value as u32 as u64;
+			}
+		}
+	}
+
+	/// Returns true if the module contains source line information.
+	///
+	/// Without it, the lcov report will be empty.
+	pub fn has_line_info(&self) -> bool {
+		!self.lines.is_empty()
+	}
+
+	/// Returns the accumulated hit counts of each basic block.
+	pub fn hits(&self) -> Vec<u64> {
+		self.hits.lock().unwrap().clone()
+	}
+
+	/// Writes the accumulated coverage as an lcov report.
+	pub fn write_lcov(&self, path: &Path) -> Result<()> {
+		let hits = self.hits.lock().unwrap();
+		let mut output = String::new();
+		for (file, lines) in &self.lines {
+			writeln!(output, "SF:{}", file.display())?;
+			let mut hit_count = 0;
+			for (line, counters) in lines {
+				let count = counters.iter().map(|idx| hits[*idx]).max().unwrap_or(0);
+				if count > 0 {
+					hit_count += 1;
+				}
+				writeln!(output, "DA:{line},{count}")?;
+			}
+			writeln!(output, "LH:{hit_count}")?;
+			writeln!(output, "LF:{}", lines.len())?;
+			writeln!(output, "end_of_record")?;
+		}
+		std::fs::write(path, output)
+			.with_context(|| format!("Failed to write coverage to {}", path.display()))
+	}
+
+	/// Returns the index of the counter for the block containing the given code offset.
+	fn counter_for_address(&self, address: u64) -> Option<usize> {
+		let idx = self
+			.functions
+			.partition_point(|function| function.start <= address)
+			.checked_sub(1)?;
+		let function = &self.functions[idx];
+		if address >= function.end || function.blocks.is_empty() {
+			return None;
+		}
+		// addresses before the first instruction (e.g. locals) belong to the first block
+		let block = function
+			.blocks
+			.partition_point(|start| *start <= address)
+			.saturating_sub(1);
+		Some(function.first_counter + block)
+	}
+
+	/// Maps source lines to the counters of the blocks that contain them, using the DWARF line tables.
+	fn map_lines(
+		&self,
+		sections: &HashMap<&str, &[u8]>,
+	) -> Result<BTreeMap<PathBuf, BTreeMap<u64, Vec<usize>>>> {
+		let dwarf = gimli::Dwarf::load(|id| -> Result<_, gimli::Error> {
+			let data = sections.get(id.name()).copied().unwrap_or_default();
+			Ok(EndianSlice::new(data, LittleEndian))
+		})?;
+		let mut lines: BTreeMap<PathBuf, BTreeMap<u64, Vec<usize>>> = BTreeMap::new();
+
+		let mut units = dwarf.units();
+		while let Some(header) = units.next()? {
+			let unit = dwarf.unit(header)?;
+			let Some(program) = unit.line_program.clone() else {
+				continue;
+			};
+			let comp_dir = unit
+				.comp_dir
+				.map(|dir| PathBuf::from(dir.to_string_lossy().into_owned()))
+				.unwrap_or_default();
+			let mut rows = program.rows();
+			while let Some((header, row)) = rows.next_row()? {
+				if row.end_sequence() {
+					continue;
+				}
+				let (Some(line), Some(file)) = (row.line(), row.file(header)) else {
+					continue;
+				};
+				let Some(counter) = self.counter_for_address(row.address()) else {
+					continue;
+				};
+				let mut path = comp_dir.clone();
+				if let Some(dir) = file.directory(header) {
+					let dir = dwarf.attr_string(&unit, dir)?;
+					path.push(dir.to_string_lossy().as_ref());
+				}
+				let name = dwarf.attr_string(&unit, file.path_name())?;
+				path.push(name.to_string_lossy().as_ref());
+				if !is_source_file(&path) {
+					continue;
+				}
+				let counters = lines
+					.entry(path)
+					.or_default()
+					.entry(line.get())
+					.or_default();
+				if !counters.contains(&counter) {
+					counters.push(counter);
+				}
+			}
+		}
+
+		Ok(lines)
+	}
+}
+
+/// Returns false for standard library and dependency sources.
+fn is_source_file(path: &Path) -> bool {
+	let path = path.to_string_lossy();
+	!(path.starts_with("/rustc/")
+		|| path.contains("/.cargo/registry/")
+		|| path.contains("/.cargo/git/")
+		|| path.contains("/rustlib/"))
+}
+
+impl ModuleMiddleware for Coverage {
+	fn generate_function_middleware(
+		&self,
+		local_function_index: LocalFunctionIndex,
+	) -> Box<dyn FunctionMiddleware> {
+		let first_global = self.first_global.get().copied().unwrap_or_default();
+		let (first_counter, block_count) = self
+			.functions
+			.get(local_function_index.index())
+			.map(|function| (function.first_counter, function.blocks.len()))
+			.unwrap_or_default();
+		Box::new(FunctionCoverage {
+			next_global: first_global + first_counter as u32,
+			end_global: first_global + (first_counter + block_count) as u32,
+			new_block: true,
+		})
+	}
+
+	fn transform_module_info(&self, module_info: &mut ModuleInfo) -> Result<(), MiddlewareError> {
+		let first_global = module_info.globals.len() as u32;
+		let _ = self.first_global.set(first_global);
+		for idx in 0..self.counter_count {
+			let global = module_info
+				.globals
+				.push(GlobalType::new(Type::I32, Mutability::Var));
+			module_info
+				.global_initializers
+				.push(GlobalInit::I32Const(0));
+			module_info.exports.insert(
+				format!("{COUNTER_PREFIX}{idx}"),
+				ExportIndex::Global(GlobalIndex::new(global.index())),
+			);
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug)]
+struct FunctionCoverage {
+	next_global: u32,
+	end_global: u32,
+	new_block: bool,
+}
+
+impl FunctionMiddleware for FunctionCoverage {
+	fn feed<'a>(
+		&mut self,
+		operator: Operator<'a>,
+		state: &mut MiddlewareReaderState<'a>,
+	) -> Result<(), MiddlewareError> {
+		if self.new_block && self.next_global < self.end_global {
+			// increment the counter for this block
+			let global_index = self.next_global;
+			state.extend(&[
+				Operator::GlobalGet { global_index },
+				Operator::I32Const { value: 1 },
+				Operator::I32Add,
+				Operator::GlobalSet { global_index },
+			]);
+			self.next_global += 1;
+		}
+		self.new_block = ends_block(&operator);
+		state.push_operator(operator);
+		Ok(())
+	}
+}
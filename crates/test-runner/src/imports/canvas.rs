@@ -1,20 +1,26 @@
-use std::io::Cursor;
+use std::{io::Cursor, path::PathBuf, sync::Arc};
 
 use crate::{
 	FFIResult, Ptr, Rid, WasmEnv,
-	libs::{ImageData, StoreItem},
+	config::Config,
+	libs::{HttpMethod, ImageData, NetRequest, StoreItem},
 };
 use aidoku::canvas::{FontWeight, PathOp};
 use euclid::Angle;
 use font_kit::{
 	family_name::FamilyName,
+	font::Font,
 	properties::{Properties, Weight},
 	source::SystemSource,
 };
 use image::{ImageBuffer, ImageReader};
 use raqote::{DrawOptions, DrawTarget, LineCap, LineJoin, Point, Source, Transform};
+use url::Url;
 use wasmer::FunctionEnvMut;
 
+/// Environment variable pointing to a directory of fonts to use instead of fetching them.
+const FONT_DIR_VAR: &str = "AIDOKU_TEST_FONT_DIR";
+
 enum Result {
 	Success,
 	InvalidContext,
@@ -27,7 +33,6 @@ enum Result {
 	InvalidStyle,
 	InvalidString,
 	InvalidFont,
-	InvalidData,
 	FontLoadFailed,
 }
 
@@ -45,8 +50,7 @@ impl From<Result> for i32 {
 			Result::InvalidStyle => -8,
 			Result::InvalidString => -9,
 			Result::InvalidFont => -10,
-			Result::InvalidData => -11,
-			Result::FontLoadFailed => -12,
+			Result::FontLoadFailed => -11,
 		}
 	}
 }
@@ -326,16 +330,48 @@ pub fn system_font(mut env: FunctionEnvMut<WasmEnv>, weight: u8) -> Rid {
 	};
 	env.data_mut().store.store(StoreItem::Font(font))
 }
-pub fn load_font(_env: FunctionEnvMut<WasmEnv>, _url_ptr: Ptr, _url_len: u32) -> FFIResult {
-	// let Ok(url) = env.data().read_string(&env, url_ptr, url_len) else {
-	// 	return Result::InvalidString.into();
-	// };
-	Result::FontLoadFailed.into()
+pub fn load_font(mut env: FunctionEnvMut<WasmEnv>, url_ptr: Ptr, url_len: u32) -> FFIResult {
+	let Ok(url) = env.data().read_string(&env, url_ptr, url_len) else {
+		return Result::InvalidString.into();
+	};
+	let Ok(url) = Url::parse(&url) else {
+		return Result::FontLoadFailed.into();
+	};
+	// prefer a local copy of the font, if one exists, so rendering is reproducible
+	let config = env.data().config.clone();
+	let data = match local_font(&config, &url) {
+		Some(data) => data,
+		None => {
+			let mut request = NetRequest::new(HttpMethod::Get);
+			request.url = Some(url);
+			if request.send(&config, &env.data().net).is_err() {
+				return Result::FontLoadFailed.into();
+			}
+			match request.response {
+				Some(response) if response.status.is_success() => response.data,
+				_ => return Result::FontLoadFailed.into(),
+			}
+		}
+	};
+	let Ok(font) = Font::from_bytes(Arc::new(data), 0) else {
+		return Result::FontLoadFailed.into();
+	};
+	env.data_mut().store.store(StoreItem::Font(font))
+}
+
+/// Reads a font with the same file name as the url from the directory in the
+/// `AIDOKU_TEST_FONT_DIR` environment variable, or the configured fonts directory.
+fn local_font(config: &Config, url: &Url) -> Option<Vec<u8>> {
+	let dir = std::env::var_os(FONT_DIR_VAR)
+		.map(PathBuf::from)
+		.or_else(|| config.fonts_dir())?;
+	let name = url.path_segments()?.next_back().filter(|s| !s.is_empty())?;
+	std::fs::read(dir.join(name)).ok()
 }
 
 pub fn new_image(mut env: FunctionEnvMut<WasmEnv>, data_ptr: Ptr, data_len: u32) -> FFIResult {
 	let Ok(data) = env.data().read_bytes(&env, data_ptr, data_len) else {
-		return Result::InvalidData.into();
+		return Result::InvalidImage.into();
 	};
 	let cursor = Cursor::new(data);
 	let Some(rgba_img) = ImageReader::new(cursor)
@@ -344,7 +380,7 @@ pub fn new_image(mut env: FunctionEnvMut<WasmEnv>, data_ptr: Ptr, data_len: u32)
 		.and_then(|r| r.decode().ok())
 		.map(|img| img.to_rgb8())
 	else {
-		return Result::InvalidData.into();
+		return Result::InvalidImage.into();
 	};
 	let width = rgba_img.width() as i32;
 	let height = rgba_img.height() as i32;
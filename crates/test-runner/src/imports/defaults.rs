@@ -76,8 +76,14 @@ pub fn set(
 	}
 
 	let default_kind: DefaultKind = kind.into();
-	let Ok(data) = env.data().read_item_bytes(&env, value_ptr) else {
-		return Result::FailedDecoding.into();
+	// null values are passed without a pointer
+	let data = if matches!(default_kind, DefaultKind::Null) {
+		Vec::new()
+	} else {
+		let Ok(data) = env.data().read_item_bytes(&env, value_ptr) else {
+			return Result::FailedDecoding.into();
+		};
+		data
 	};
 
 	let default_value = match default_kind {
@@ -2,6 +2,7 @@ use crate::{
 	FFIResult, Ptr, Rid, WasmEnv,
 	libs::{HtmlDocument, HtmlElement, HtmlNode, StoreItem},
 };
+use aidoku::helpers::encoding;
 use scraper::Selector;
 use wasmer::FunctionEnvMut;
 
@@ -62,12 +63,13 @@ pub fn parse(
 	base_url_ptr: Ptr,
 	base_url_len: u32,
 ) -> FFIResult {
-	let Ok(text) = env.data().read_string(&env, html_ptr, html_len) else {
+	let Ok(data) = env.data().read_bytes(&env, html_ptr, html_len) else {
 		return Result::InvalidString.into();
 	};
 	let Ok(base_url_string) = env.data().read_string(&env, base_url_ptr, base_url_len) else {
 		return Result::InvalidString.into();
 	};
+	let text = encoding::decode(&data, None);
 	let document = HtmlDocument::parse(&text, Some(&base_url_string));
 	env.data_mut()
 		.store
@@ -80,12 +82,13 @@ pub fn parse_fragment(
 	base_url_ptr: Ptr,
 	base_url_len: u32,
 ) -> FFIResult {
-	let Ok(text) = env.data().read_string(&env, html_ptr, html_len) else {
+	let Ok(data) = env.data().read_bytes(&env, html_ptr, html_len) else {
 		return Result::InvalidString.into();
 	};
 	let Ok(base_url_string) = env.data().read_string(&env, base_url_ptr, base_url_len) else {
 		return Result::InvalidString.into();
 	};
+	let text = encoding::decode(&data, None);
 	let document = HtmlDocument::parse_fragment(&text, Some(&base_url_string));
 	env.data_mut()
 		.store
@@ -158,7 +161,7 @@ pub fn kind(mut env: FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
 	}
 	match item {
 		StoreItem::HtmlElement(element) => kind_from_node(&element.0),
-		StoreItem::HtmlNode(node) => kind_from_node(&node),
+		StoreItem::HtmlNode(node) => kind_from_node(node),
 		StoreItem::HtmlDocument(_) => Kind::Document,
 		StoreItem::HtmlElementList(_) => Kind::ElementList,
 		_ => Kind::Unknown,
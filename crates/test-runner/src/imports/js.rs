@@ -1,5 +1,7 @@
-use crate::{FFIResult, Ptr, Rid, WasmEnv, libs::StoreItem};
-use boa_engine::{JsString, Source};
+use crate::{
+	FFIResult, Ptr, Rid, WasmEnv,
+	libs::{JsContext, StoreItem},
+};
 use wasmer::FunctionEnvMut;
 
 enum Result {
@@ -32,7 +34,8 @@ impl From<Result> for i32 {
 }
 
 pub fn context_create(mut env: FunctionEnvMut<WasmEnv>) -> Rid {
-	let context = boa_engine::Context::default();
+	let now = env.data().config.now().timestamp_millis().max(0) as u64;
+	let context = JsContext::with_time(now);
 	env.data_mut()
 		.store
 		.store(StoreItem::JsContext(Box::new(context)))
@@ -54,26 +57,50 @@ pub fn context_eval(
 	else {
 		return Result::InvalidContext.into();
 	};
-	let src = Source::from_bytes(&string);
-	let Ok(result) = context.eval(src) else {
-		return Result::MissingResult.into();
-	};
-	let Some(result_string) = result
-		.to_string(context)
+	let result_string = context
+		.eval(&string)
 		.ok()
-		.and_then(|s| s.to_std_string().ok())
-	else {
+		.and_then(|result| context.to_string(&result));
+	// route console output into the test's stdout
+	let output = context.take_console_output();
+	env.data_mut().write_stdout(&output);
+	let Some(result_string) = result_string else {
 		return Result::MissingResult.into();
 	};
 	env.data_mut().store.store(StoreItem::String(result_string))
 }
 pub fn context_eval_async(
-	_env: FunctionEnvMut<WasmEnv>,
-	_context: Rid,
-	_string_ptr: u32,
-	_len: u32,
+	mut env: FunctionEnvMut<WasmEnv>,
+	rid: Rid,
+	string_ptr: Ptr,
+	len: u32,
 ) -> FFIResult {
-	-1
+	let Ok(string) = env.data().read_string(&env, string_ptr, len) else {
+		return Result::InvalidString.into();
+	};
+	let Some(context) = env
+		.data_mut()
+		.store
+		.get_mut(rid)
+		.and_then(|item| item.as_js_context())
+	else {
+		return Result::InvalidContext.into();
+	};
+	let result = context.eval_async(&string);
+	let mut output = context.take_console_output();
+	let result_string = match result {
+		Ok(result) => context.to_string(&result),
+		Err(err) => {
+			// log the rejection reason so it shows up in the test output
+			output.push_str(&format!("js error: {err}\n"));
+			None
+		}
+	};
+	env.data_mut().write_stdout(&output);
+	let Some(result_string) = result_string else {
+		return Result::MissingResult.into();
+	};
+	env.data_mut().store.store(StoreItem::String(result_string))
 }
 pub fn context_get(
 	mut env: FunctionEnvMut<WasmEnv>,
@@ -92,15 +119,10 @@ pub fn context_get(
 	else {
 		return Result::InvalidContext.into();
 	};
-	let key: JsString = string.into();
-	let Ok(result) = context.global_object().get(key, context) else {
+	let Ok(result) = context.get(&string) else {
 		return Result::MissingResult.into();
 	};
-	let Some(result_string) = result
-		.to_string(context)
-		.ok()
-		.and_then(|s| s.to_std_string().ok())
-	else {
+	let Some(result_string) = context.to_string(&result) else {
 		return Result::MissingResult.into();
 	};
 	env.data_mut().store.store(StoreItem::String(result_string))
@@ -162,6 +184,7 @@ pub fn webview_add_user_script(
 pub fn webview_get_cookies(_env: FunctionEnvMut<WasmEnv>, _webview: Rid) -> FFIResult {
 	-1
 }
+#[allow(clippy::too_many_arguments)]
 pub fn webview_delete_cookie(
 	_env: FunctionEnvMut<WasmEnv>,
 	_webview: Rid,
@@ -8,6 +8,7 @@ mod html;
 mod js;
 mod net;
 mod std;
+mod test;
 
 pub fn generate_imports(store: &mut Store, env: &FunctionEnv<WasmEnv>) -> Imports {
 	imports! {
@@ -117,21 +118,38 @@ pub fn generate_imports(store: &mut Store, env: &FunctionEnv<WasmEnv>) -> Import
 			"init" => Function::new_typed_with_env(store, env, net::init),
 			"send" => Function::new_typed_with_env(store, env, net::send),
 			"send_all" => Function::new_typed_with_env(store, env, net::send_all),
+			"start" => Function::new_typed_with_env(store, env, net::start),
+			"poll" => Function::new_typed_with_env(store, env, net::poll),
+			"wait" => Function::new_typed_with_env(store, env, net::wait),
+			"wait_any" => Function::new_typed_with_env(store, env, net::wait_any),
 
 			"set_url" => Function::new_typed_with_env(store, env, net::set_url),
 			"set_header" => Function::new_typed_with_env(store, env, net::set_header),
 			"set_body" => Function::new_typed_with_env(store, env, net::set_body),
 			"set_timeout" => Function::new_typed_with_env(store, env, net::set_timeout),
+			"set_redirect_policy" => Function::new_typed_with_env(store, env, net::set_redirect_policy),
 
 			"data_len" => Function::new_typed_with_env(store, env, net::data_len),
 			"read_data" => Function::new_typed_with_env(store, env, net::read_data),
+			"read_data_at" => Function::new_typed_with_env(store, env, net::read_data_at),
 			"get_image" => Function::new_typed_with_env(store, env, net::get_image),
 			"get_status_code" => Function::new_typed_with_env(store, env, net::get_status_code),
 			"get_url" => Function::new_typed_with_env(store, env, net::get_url),
+			"get_redirects" => Function::new_typed_with_env(store, env, net::get_redirects),
 			"get_header" => Function::new_typed_with_env(store, env, net::get_header),
+			"get_headers" => Function::new_typed_with_env(store, env, net::get_headers),
 			"html" => Function::new_typed_with_env(store, env, net::html),
 
+			"get_cookies" => Function::new_typed_with_env(store, env, net::get_cookies),
+			"set_cookie" => Function::new_typed_with_env(store, env, net::set_cookie),
+			"delete_cookie" => Function::new_typed_with_env(store, env, net::delete_cookie),
+			"delete_cookies" => Function::new_typed_with_env(store, env, net::delete_cookies),
+
 			"set_rate_limit" => Function::new_typed_with_env(store, env, net::set_rate_limit),
+			"set_host_rate_limit" => Function::new_typed_with_env(store, env, net::set_host_rate_limit),
+		},
+		"test" => {
+			"assert_snapshot" => Function::new_typed_with_env(store, env, test::assert_snapshot),
 		},
 	}
 }
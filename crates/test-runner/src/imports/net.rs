@@ -1,15 +1,16 @@
 use crate::{
 	FFIResult, Ptr, Rid, WasmEnv,
-	libs::{HtmlDocument, HttpMethod, ImageData, NetRequest, NetResponse, StoreItem},
+	libs::{
+		Cookie, HtmlDocument, HttpMethod, ImageData, NetRequest, RateLimit, SendError, StoreItem,
+	},
 };
+use aidoku::helpers::encoding;
 use image::ImageReader;
-use reqwest::header::{HeaderName, HeaderValue, USER_AGENT};
+use reqwest::header::{CONTENT_TYPE, HeaderName, HeaderValue};
 use std::{io::Cursor, str::FromStr};
 use url::Url;
 use wasmer::FunctionEnvMut;
 
-const DEFAULT_USER_AGENT: &str = "Aidoku/1 CFNetwork/3826.500.131 Darwin/24.5.0";
-
 enum Result {
 	Success,
 	InvalidDescriptor,
@@ -24,6 +25,7 @@ enum Result {
 	RequestError,
 	FailedMemoryWrite,
 	NotAnImage,
+	InvalidRateLimit,
 }
 
 impl From<Result> for i32 {
@@ -42,6 +44,7 @@ impl From<Result> for i32 {
 			Result::RequestError => -10,
 			Result::FailedMemoryWrite => -11,
 			Result::NotAnImage => -12,
+			Result::InvalidRateLimit => -13,
 		}
 	}
 }
@@ -65,64 +68,23 @@ pub fn init(mut env: FunctionEnvMut<WasmEnv>, method: u8) -> FFIResult {
 		.store(StoreItem::Request(Box::new(request)))
 }
 fn common_send(env: &mut FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
-	let Some(request) = env
-		.data_mut()
-		.store
-		.get_mut(rid)
-		.and_then(|item| item.as_request())
-	else {
+	let data = env.data_mut();
+	let Some(request) = data.store.get_mut(rid).and_then(|item| item.as_request()) else {
 		return Result::InvalidDescriptor.into();
 	};
-	// add a default user agent if none is provided
-	if !request.headers.contains_key(USER_AGENT) {
-		let default_ua = HeaderValue::from_static(DEFAULT_USER_AGENT);
-		request.headers.insert(USER_AGENT, default_ua);
-	}
-	let Some(url) = request.url.as_ref() else {
-		return Result::InvalidUrl.into();
-	};
-	let mut builder = reqwest::blocking::Client::new()
-		.request(
-			match request.method {
-				HttpMethod::Get => reqwest::Method::GET,
-				HttpMethod::Post => reqwest::Method::POST,
-				HttpMethod::Put => reqwest::Method::PUT,
-				HttpMethod::Delete => reqwest::Method::DELETE,
-				HttpMethod::Head => reqwest::Method::HEAD,
-				HttpMethod::Patch => reqwest::Method::PATCH,
-				HttpMethod::Options => reqwest::Method::OPTIONS,
-				HttpMethod::Connect => reqwest::Method::CONNECT,
-				HttpMethod::Trace => reqwest::Method::TRACE,
-			},
-			url.to_string(),
-		)
-		.headers(request.headers.clone());
-	if let Some(body) = request.body.take() {
-		builder = builder.body(body);
-	}
-	if let Some(timeout) = request.timeout.take() {
-		let secs = timeout.trunc() as u64;
-		let nanos = ((timeout.fract()) * 1_000_000_000.0).round() as u32;
-		builder = builder.timeout(std::time::Duration::new(secs, nanos));
+	send_result(request.send(&data.config, &data.net))
+}
+fn send_result(result: std::result::Result<(), SendError>) -> FFIResult {
+	match result {
+		Ok(()) => Result::Success.into(),
+		Err(SendError::InvalidUrl) => Result::InvalidUrl.into(),
+		Err(SendError::RequestError | SendError::Blocked) => Result::RequestError.into(),
 	}
-	// make a blocking request with reqwest
-	let Ok(response) = builder.send() else {
-		return Result::RequestError.into();
-	};
-	let url = response.url().clone();
-	let status = response.status();
-	let headers = response.headers().clone();
-	let Ok(bytes) = response.bytes() else {
-		return Result::RequestError.into();
-	};
-	request.response = Some(NetResponse {
-		url,
-		status,
-		headers,
-		data: bytes.into(),
-	});
-	Result::Success.into()
 }
+/// Sends a request, replacing any previous response.
+///
+/// Sources re-send the same rid when retrying, so the request's url, headers, body and
+/// timeout must be kept after sending, like the app does.
 pub fn send(mut env: FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
 	common_send(&mut env, rid)
 }
@@ -148,6 +110,61 @@ pub fn send_all(mut env: FunctionEnvMut<WasmEnv>, rid_ptr: Ptr, len: u32) -> FFI
 	}
 }
 
+pub fn start(mut env: FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
+	let data = env.data_mut();
+	let Some(request) = data.store.get_mut(rid).and_then(|item| item.as_request()) else {
+		return Result::InvalidDescriptor.into();
+	};
+	let pending = request.send_in_background(data.config.clone(), data.net.clone());
+	data.pending.insert(rid, pending);
+	Result::Success.into()
+}
+pub fn poll(mut env: FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
+	let data = env.data_mut();
+	if let Some(pending) = data.pending.get(&rid) {
+		return pending.is_finished() as FFIResult;
+	}
+	// requests that aren't pending have finished, or were never started
+	match data.store.get_mut(rid).and_then(|item| item.as_request()) {
+		Some(_) => 1,
+		None => Result::InvalidDescriptor.into(),
+	}
+}
+pub fn wait(mut env: FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
+	let data = env.data_mut();
+	let Some(pending) = data.pending.remove(&rid) else {
+		return Result::MissingResponse.into();
+	};
+	let (response, result) = pending
+		.join()
+		.unwrap_or((None, Err(SendError::RequestError)));
+	let Some(request) = data.store.get_mut(rid).and_then(|item| item.as_request()) else {
+		return Result::InvalidDescriptor.into();
+	};
+	request.response = response;
+	send_result(result)
+}
+pub fn wait_any(mut env: FunctionEnvMut<WasmEnv>, rid_ptr: Ptr, len: u32) -> FFIResult {
+	let Ok(rids) = env.data().read_values::<Rid>(&env, rid_ptr, len) else {
+		return Result::InvalidDescriptor.into();
+	};
+	if rids.is_empty() {
+		return Result::InvalidDescriptor.into();
+	}
+	let data = env.data_mut();
+	loop {
+		let finished = rids.iter().position(|rid| {
+			data.pending
+				.get(rid)
+				.is_none_or(|pending| pending.is_finished())
+		});
+		if let Some(idx) = finished {
+			return idx as FFIResult;
+		}
+		std::thread::sleep(std::time::Duration::from_millis(1));
+	}
+}
+
 pub fn set_url(mut env: FunctionEnvMut<WasmEnv>, rid: Rid, ptr: Ptr, len: u32) -> FFIResult {
 	let Ok(string) = env.data().read_string(&env, ptr, len) else {
 		return Result::InvalidString.into();
@@ -225,6 +242,23 @@ pub fn set_timeout(mut env: FunctionEnvMut<WasmEnv>, rid: Rid, value: f64) -> FF
 	Result::Success.into()
 }
 
+pub fn set_redirect_policy(
+	mut env: FunctionEnvMut<WasmEnv>,
+	rid: Rid,
+	max_redirects: i32,
+) -> FFIResult {
+	let Some(request) = env
+		.data_mut()
+		.store
+		.get_mut(rid)
+		.and_then(|item| item.as_request())
+	else {
+		return Result::InvalidDescriptor.into();
+	};
+	request.max_redirects = usize::try_from(max_redirects).ok();
+	Result::Success.into()
+}
+
 pub fn data_len(mut env: FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
 	let Some(request) = env
 		.data_mut()
@@ -267,6 +301,33 @@ pub fn read_data(mut env: FunctionEnvMut<WasmEnv>, rid: Rid, buffer: Ptr, size:
 		Result::FailedMemoryWrite.into()
 	}
 }
+pub fn read_data_at(
+	mut env: FunctionEnvMut<WasmEnv>,
+	rid: Rid,
+	offset: u32,
+	buffer: Ptr,
+	size: u32,
+) -> FFIResult {
+	let Some(request) = env
+		.data_mut()
+		.store
+		.get_mut(rid)
+		.and_then(|item| item.as_request())
+	else {
+		return Result::InvalidDescriptor.into();
+	};
+	let Some(response) = request.response.as_ref() else {
+		return Result::MissingResponse.into();
+	};
+	let start = (offset as usize).min(response.data.len());
+	let end = start.saturating_add(size as usize).min(response.data.len());
+	let data = response.data[start..end].to_vec();
+	if env.data().write_buffer(&env, buffer, &data).is_err() {
+		Result::FailedMemoryWrite.into()
+	} else {
+		data.len() as FFIResult
+	}
+}
 pub fn get_image(mut env: FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
 	let Some(request) = env
 		.data_mut()
@@ -333,6 +394,28 @@ pub fn get_url(mut env: FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
 	request.response = Some(response);
 	env.data_mut().store.store(StoreItem::String(url))
 }
+pub fn get_redirects(mut env: FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
+	let Some(request) = env
+		.data_mut()
+		.store
+		.get_mut(rid)
+		.and_then(|item| item.as_request())
+	else {
+		return Result::InvalidDescriptor.into();
+	};
+	let Some(response) = request.response.as_ref() else {
+		return Result::MissingResponse.into();
+	};
+	let redirects = response
+		.redirects
+		.iter()
+		.map(|url| url.to_string())
+		.collect::<Vec<String>>();
+	env.data_mut()
+		.store
+		.store_encoded(&redirects)
+		.unwrap_or(Result::MissingData.into())
+}
 pub fn get_header(
 	mut env: FunctionEnvMut<WasmEnv>,
 	rid: Rid,
@@ -371,6 +454,34 @@ pub fn get_header(
 	};
 	env.data_mut().store.store(StoreItem::String(value))
 }
+
+pub fn get_headers(mut env: FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
+	let Some(request) = env
+		.data_mut()
+		.store
+		.get_mut(rid)
+		.and_then(|item| item.as_request())
+	else {
+		return Result::InvalidDescriptor.into();
+	};
+	let Some(response) = request.response.as_ref() else {
+		return Result::MissingResponse.into();
+	};
+	let headers = response
+		.headers
+		.iter()
+		.map(|(name, value)| {
+			(
+				name.to_string(),
+				String::from_utf8_lossy(value.as_bytes()).into_owned(),
+			)
+		})
+		.collect::<Vec<(String, String)>>();
+	env.data_mut()
+		.store
+		.store_encoded(&headers)
+		.unwrap_or(Result::MissingData.into())
+}
 pub fn html(mut env: FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
 	let Some(request) = env
 		.data_mut()
@@ -383,17 +494,112 @@ pub fn html(mut env: FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
 	let Some(response) = request.response.take() else {
 		return Result::MissingResponse.into();
 	};
-	let document = std::str::from_utf8(&response.data)
-		.map(|text| HtmlDocument::parse(text, Some(response.url.as_str())));
+	// decode using the charset from the content type or a meta tag
+	let content_type = response
+		.headers
+		.get(CONTENT_TYPE)
+		.and_then(|value| value.to_str().ok());
+	let text = encoding::decode(&response.data, content_type);
+	let document = HtmlDocument::parse(&text, Some(response.url.as_str()));
 	request.response = Some(response);
-	let Ok(document) = document else {
+	env.data_mut()
+		.store
+		.store(StoreItem::HtmlDocument(document))
+}
+
+pub fn get_cookies(
+	mut env: FunctionEnvMut<WasmEnv>,
+	domain_ptr: Ptr,
+	domain_len: u32,
+) -> FFIResult {
+	let Ok(domain) = env.data().read_string(&env, domain_ptr, domain_len) else {
 		return Result::InvalidString.into();
 	};
+	let now = env.data().config.now().timestamp();
+	let cookies = env.data().net.cookies.lock().unwrap().get(&domain, now);
 	env.data_mut()
 		.store
-		.store(StoreItem::HtmlDocument(document))
+		.store_encoded(&cookies)
+		.unwrap_or(Result::MissingData.into())
+}
+pub fn set_cookie(env: FunctionEnvMut<WasmEnv>, cookie_ptr: Ptr, cookie_len: u32) -> FFIResult {
+	let Some(cookie) = env
+		.data()
+		.read_bytes(&env, cookie_ptr, cookie_len)
+		.ok()
+		.and_then(|data| postcard::from_bytes::<Cookie>(&data).ok())
+	else {
+		return Result::MissingData.into();
+	};
+	let now = env.data().config.now().timestamp();
+	env.data().net.cookies.lock().unwrap().set(cookie, now);
+	Result::Success.into()
+}
+pub fn delete_cookie(
+	env: FunctionEnvMut<WasmEnv>,
+	name_ptr: Ptr,
+	name_len: u32,
+	domain_ptr: Ptr,
+	domain_len: u32,
+) -> FFIResult {
+	let Ok(name) = env.data().read_string(&env, name_ptr, name_len) else {
+		return Result::InvalidString.into();
+	};
+	let Ok(domain) = env.data().read_string(&env, domain_ptr, domain_len) else {
+		return Result::InvalidString.into();
+	};
+	env.data()
+		.net
+		.cookies
+		.lock()
+		.unwrap()
+		.delete(Some(&name), &domain);
+	Result::Success.into()
 }
 
-pub fn set_rate_limit(_env: FunctionEnvMut<WasmEnv>, _permits: i32, _period: i32, _unit: i32) {
-	// leaving this function unimplemented for now
+pub fn delete_cookies(env: FunctionEnvMut<WasmEnv>, domain_ptr: Ptr, domain_len: u32) -> FFIResult {
+	let Ok(domain) = env.data().read_string(&env, domain_ptr, domain_len) else {
+		return Result::InvalidString.into();
+	};
+	env.data().net.cookies.lock().unwrap().delete(None, &domain);
+	Result::Success.into()
+}
+
+pub fn set_rate_limit(env: FunctionEnvMut<WasmEnv>, permits: i32, period: i32, unit: i32) {
+	// zero permits removes the limit, and invalid limits are ignored
+	let limit = match permits {
+		0 => None,
+		_ => match RateLimit::from_ffi(permits, period, unit) {
+			Some(limit) => Some(limit),
+			None => return,
+		},
+	};
+	env.data().net.rate_limits.lock().unwrap().set_global(limit);
+}
+pub fn set_host_rate_limit(
+	env: FunctionEnvMut<WasmEnv>,
+	pattern_ptr: Ptr,
+	pattern_len: u32,
+	permits: i32,
+	period: i32,
+	unit: i32,
+) -> FFIResult {
+	let Ok(pattern) = env.data().read_string(&env, pattern_ptr, pattern_len) else {
+		return Result::InvalidString.into();
+	};
+	// zero permits removes the limit for the pattern
+	let limit = match permits {
+		0 => None,
+		_ => match RateLimit::from_ffi(permits, period, unit) {
+			Some(limit) => Some(limit),
+			None => return Result::InvalidRateLimit.into(),
+		},
+	};
+	env.data()
+		.net
+		.rate_limits
+		.lock()
+		.unwrap()
+		.set(&pattern, limit);
+	Result::Success.into()
 }
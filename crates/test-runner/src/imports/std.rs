@@ -26,7 +26,10 @@ impl From<Result> for i32 {
 }
 
 pub fn destroy(mut env: FunctionEnvMut<WasmEnv>, rid: Rid) {
-	env.data_mut().store.remove(rid);
+	let data = env.data_mut();
+	data.store.remove(rid);
+	// a request dropped without waiting keeps sending in the background, but is forgotten
+	data.pending.remove(&rid);
 }
 
 pub fn buffer_len(env: FunctionEnvMut<WasmEnv>, rid: Rid) -> FFIResult {
@@ -52,14 +55,20 @@ pub fn read_buffer(env: FunctionEnvMut<WasmEnv>, rid: Rid, ptr: Ptr, size: u32)
 	}
 }
 
-pub fn current_date(_env: FunctionEnvMut<WasmEnv>) -> f64 {
-	use chrono::Utc;
-	Utc::now().timestamp() as f64
+pub fn current_date(env: FunctionEnvMut<WasmEnv>) -> f64 {
+	env.data().config.now().timestamp() as f64
 }
 
-pub fn utc_offset(_env: FunctionEnvMut<WasmEnv>) -> i64 {
-	use chrono::Local;
-	Local::now().offset().utc_minus_local() as i64
+pub fn utc_offset(env: FunctionEnvMut<WasmEnv>) -> i64 {
+	use chrono::{Local, Offset};
+	let config = &env.data().config;
+	match config.clock.timezone {
+		Some(timezone) => {
+			let offset = config.now().with_timezone(&timezone).offset().fix();
+			offset.utc_minus_local() as i64
+		}
+		None => Local::now().offset().utc_minus_local() as i64,
+	}
 }
 
 pub fn parse_date(
@@ -90,6 +99,7 @@ pub fn parse_date(
 	let timezone: chrono_tz::Tz = timezone_string
 		.as_deref()
 		.and_then(|tz_str| tz_str.parse().ok())
+		.or(env.data().config.clock.timezone)
 		.unwrap_or(chrono_tz::UTC);
 
 	use chrono::NaiveDateTime;
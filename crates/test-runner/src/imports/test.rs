@@ -0,0 +1,73 @@
+use crate::{FFIResult, Ptr, WasmEnv, libs::SnapshotResult, libs::Snapshots};
+use wasmer::FunctionEnvMut;
+
+enum Result {
+	Success,
+	InvalidString,
+	InvalidJson,
+	Mismatch,
+	FailedWrite,
+}
+
+impl From<Result> for i32 {
+	fn from(result: Result) -> Self {
+		match result {
+			Result::Success => 0,
+			Result::InvalidString => -1,
+			Result::InvalidJson => -2,
+			Result::Mismatch => -3,
+			Result::FailedWrite => -4,
+		}
+	}
+}
+
+pub fn assert_snapshot(
+	mut env: FunctionEnvMut<WasmEnv>,
+	name_ptr: Ptr,
+	name_len: u32,
+	data_ptr: Ptr,
+	data_len: u32,
+) -> FFIResult {
+	let Ok(name) = env.data().read_string(&env, name_ptr, name_len) else {
+		return Result::InvalidString.into();
+	};
+	let Ok(data) = env.data().read_bytes(&env, data_ptr, data_len) else {
+		return Result::InvalidString.into();
+	};
+	let Ok(value) = serde_json::from_slice(&data) else {
+		return Result::InvalidJson.into();
+	};
+	let test_name = env.data().test_name.clone().unwrap_or_default();
+	let snapshots = Snapshots::from_config(&env.data().config);
+	let path = snapshots.path(&test_name, &name);
+	match snapshots.assert(&test_name, &name, &value) {
+		Ok(SnapshotResult::Matched) => Result::Success.into(),
+		Ok(SnapshotResult::Created) => {
+			env.data_mut().write_stdout(&format!(
+				"created snapshot `{name}` at {}\n",
+				path.display()
+			));
+			Result::Success.into()
+		}
+		Ok(SnapshotResult::Updated) => {
+			env.data_mut().write_stdout(&format!(
+				"updated snapshot `{name}` at {}\n",
+				path.display()
+			));
+			Result::Success.into()
+		}
+		Ok(SnapshotResult::Mismatched(diff)) => {
+			env.data_mut().write_stdout(&format!(
+				"snapshot `{name}` does not match {}:\n{diff}",
+				path.display()
+			));
+			Result::Mismatch.into()
+		}
+		Err(err) => {
+			env.data_mut().write_stdout(&format!(
+				"error: failed to write snapshot `{name}`: {err}\n"
+			));
+			Result::FailedWrite.into()
+		}
+	}
+}
@@ -1,5 +1,9 @@
 #![doc = include_str!("../README.md")]
+pub mod bench;
+pub mod config;
+pub mod coverage;
 pub mod imports;
 pub mod libs;
+pub mod profile;
 
 pub use libs::{FFIResult, Ptr, Rid, WasmEnv};
@@ -0,0 +1,165 @@
+use chrono::DateTime;
+use reqwest::header::{HeaderMap, SET_COOKIE};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// A cookie, encoded the same way as `aidoku::imports::net::Cookie`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cookie {
+	pub name: String,
+	pub value: String,
+	/// The expiration date as a Unix timestamp, or none for session cookies.
+	pub expires_date: Option<i64>,
+	pub domain: String,
+	pub path: String,
+	pub is_secure: bool,
+	pub is_http_only: bool,
+}
+
+impl Cookie {
+	/// Parses the value of a `Set-Cookie` header received from the given url.
+	pub fn parse(set_cookie: &str, url: &Url, now: i64) -> Option<Self> {
+		let mut attributes = set_cookie.split(';');
+		let (name, value) = attributes.next()?.split_once('=')?;
+		let name = name.trim();
+		if name.is_empty() {
+			return None;
+		}
+		let mut cookie = Self {
+			name: name.into(),
+			value: value.trim().trim_matches('"').into(),
+			expires_date: None,
+			domain: url.host_str()?.to_ascii_lowercase(),
+			path: "/".into(),
+			is_secure: false,
+			is_http_only: false,
+		};
+		let mut max_age = None;
+		for attribute in attributes {
+			let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+			let value = value.trim();
+			match key.trim().to_ascii_lowercase().as_str() {
+				"expires" => {
+					if let Ok(date) = DateTime::parse_from_rfc2822(&value.replace('-', " ")) {
+						cookie.expires_date = Some(date.timestamp());
+					}
+				}
+				"max-age" => max_age = value.parse::<i64>().ok(),
+				"domain" if !value.is_empty() => {
+					let domain = value.trim_start_matches('.').to_ascii_lowercase();
+					// cookies can only be set for the response's host and its parent domains
+					if !domain_matches(&cookie.domain, &domain) {
+						return None;
+					}
+					cookie.domain = domain;
+				}
+				"path" if value.starts_with('/') => cookie.path = value.into(),
+				"secure" => cookie.is_secure = true,
+				"httponly" => cookie.is_http_only = true,
+				_ => {}
+			}
+		}
+		// max-age takes precedence over expires
+		if let Some(max_age) = max_age {
+			cookie.expires_date = Some(now.saturating_add(max_age.max(0)));
+		}
+		Some(cookie)
+	}
+
+	/// Returns true if the cookie should be sent to the given host.
+	pub fn matches_domain(&self, host: &str) -> bool {
+		domain_matches(&host.to_ascii_lowercase(), &self.domain)
+	}
+
+	/// Returns true if the cookie should be sent with a request to the given url.
+	pub fn matches_url(&self, url: &Url) -> bool {
+		let path = url.path();
+		url.host_str().is_some_and(|host| self.matches_domain(host))
+			&& (!self.is_secure || url.scheme() == "https")
+			&& (path == self.path
+				|| path.starts_with(&self.path)
+					&& (self.path.ends_with('/') || path[self.path.len()..].starts_with('/')))
+	}
+
+	pub fn is_expired(&self, now: i64) -> bool {
+		self.expires_date.is_some_and(|date| date <= now)
+	}
+}
+
+/// Returns true if the host is the domain or one of its subdomains.
+fn domain_matches(host: &str, domain: &str) -> bool {
+	host == domain
+		|| host
+			.strip_suffix(domain)
+			.is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// The shared cookie store that requests send and receive cookies through.
+#[derive(Debug, Clone, Default)]
+pub struct CookieStore {
+	cookies: Vec<Cookie>,
+}
+
+impl CookieStore {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Returns the unexpired cookies for the given domain, including its parent domains.
+	pub fn get(&self, domain: &str, now: i64) -> Vec<Cookie> {
+		self.cookies
+			.iter()
+			.filter(|cookie| cookie.matches_domain(domain) && !cookie.is_expired(now))
+			.cloned()
+			.collect()
+	}
+
+	/// Adds a cookie, replacing any cookie with the same name, domain and path.
+	///
+	/// Expired cookies are removed instead.
+	pub fn set(&mut self, cookie: Cookie, now: i64) {
+		self.cookies.retain(|existing| {
+			existing.name != cookie.name
+				|| existing.domain != cookie.domain
+				|| existing.path != cookie.path
+		});
+		if !cookie.is_expired(now) {
+			self.cookies.push(cookie);
+		}
+	}
+
+	/// Removes the cookies with the given name for a domain, or all of the domain's cookies.
+	///
+	/// Cookies of the domain and its subdomains are removed, but not those of its parent
+	/// domains, even though they would be sent to it.
+	pub fn delete(&mut self, name: Option<&str>, domain: &str) {
+		let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+		self.cookies.retain(|cookie| {
+			!domain_matches(&cookie.domain, &domain) || name.is_some_and(|name| name != cookie.name)
+		});
+	}
+
+	/// Returns the value of the `Cookie` header for a request to the given url.
+	pub fn header(&self, url: &Url, now: i64) -> Option<String> {
+		let cookies = self
+			.cookies
+			.iter()
+			.filter(|cookie| cookie.matches_url(url) && !cookie.is_expired(now))
+			.map(|cookie| format!("{}={}", cookie.name, cookie.value))
+			.collect::<Vec<_>>();
+		(!cookies.is_empty()).then(|| cookies.join("; "))
+	}
+
+	/// Stores the cookies set by a response from the given url.
+	pub fn store_response(&mut self, url: &Url, headers: &HeaderMap, now: i64) {
+		for value in headers.get_all(SET_COOKIE) {
+			if let Some(cookie) = value
+				.to_str()
+				.ok()
+				.and_then(|value| Cookie::parse(value, url, now))
+			{
+				self.set(cookie, now);
+			}
+		}
+	}
+}
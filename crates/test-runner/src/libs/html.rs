@@ -25,7 +25,7 @@ pub struct HtmlElementList(pub Vec<HtmlElement>);
 impl HtmlDocument {
 	pub fn parse(html: &str, base_uri: Option<&str>) -> Self {
 		let html = Html::parse_document(html);
-		let base_uri = base_uri.and_then(|s| Url::parse(s).ok()).map(Rc::new);
+		let base_uri = resolve_base_uri(&html, base_uri);
 		Self {
 			html: Rc::new(html),
 			base_uri,
@@ -34,7 +34,7 @@ impl HtmlDocument {
 
 	pub fn parse_fragment(html: &str, base_uri: Option<&str>) -> Self {
 		let html = Html::parse_fragment(html);
-		let base_uri = base_uri.and_then(|s| Url::parse(s).ok()).map(Rc::new);
+		let base_uri = resolve_base_uri(&html, base_uri);
 		Self {
 			html: Rc::new(html),
 			base_uri,
@@ -58,6 +58,24 @@ impl HtmlDocument {
 	}
 }
 
+/// Returns the uri that relative urls in a document are resolved against.
+///
+/// Like jsoup, the first `<base href>` in the document takes precedence over the document url,
+/// and is itself resolved against the document url.
+fn resolve_base_uri(html: &Html, document_url: Option<&str>) -> Option<Rc<Url>> {
+	let document_url = document_url.and_then(|url| Url::parse(url).ok());
+	let base_href = Selector::parse("base[href]")
+		.ok()
+		.and_then(|selector| html.select(&selector).next())
+		.and_then(|element| element.attr("href"));
+	let base_url = match (base_href, &document_url) {
+		(Some(href), Some(url)) => url.join(href).ok(),
+		(Some(href), None) => Url::parse(href).ok(),
+		(None, _) => None,
+	};
+	base_url.or(document_url).map(Rc::new)
+}
+
 impl HtmlNode {
 	pub fn is_element(&self) -> bool {
 		let Some(node) = self.html.tree.get(self.id) else {
@@ -122,11 +140,18 @@ impl HtmlNode {
 
 	pub fn text(&self) -> Option<String> {
 		let node = self.html.tree.get(self.id)?;
-		if let Some(text) = node.value().as_text() {
-			Some(text.deref().into())
-		} else {
-			None
+		node.value().as_text().map(|text| text.deref().into())
+	}
+
+	/// Resolves a url against the base uri of the document.
+	pub fn absolute_url(&self, value: &str) -> Option<String> {
+		// if the value is already a url, return it
+		if let Ok(url) = Url::parse(value) {
+			return Some(url.to_string());
 		}
+		// otherwise, try to join it with the base uri
+		let base_uri = self.base_uri.as_ref()?;
+		base_uri.join(value).ok().map(|url| url.to_string())
 	}
 
 	fn child_node(&self, id: NodeId) -> HtmlNode {
@@ -161,29 +186,16 @@ impl HtmlElement {
 			.map(|element| self.child(element.id()))
 	}
 
+	/// Returns the value of an attribute.
+	///
+	/// If the name is prefixed with `abs:`, the value is resolved to an absolute url,
+	/// and `None` is returned if that isn't possible.
 	pub fn attr(&self, name: &str) -> Option<String> {
-		let has_abs_prefix = name.starts_with("abs:");
-		let name = if has_abs_prefix { &name[4..] } else { name };
 		let node = self.0.html.tree.get(self.0.id)?;
 		let element = ElementRef::wrap(node)?;
-		let attr = element.attr(name).map(|value| value.to_string());
-		if has_abs_prefix {
-			if let Some(base_uri) = self.0.base_uri.as_ref() {
-				attr.as_ref()
-					// if the attribute is already a url, return it
-					.and_then(|value| Url::parse(value).ok())
-					.map(|url| url.to_string())
-					.or_else(|| {
-						// otherwise, try to join it with the base uri
-						attr.as_ref()
-							.and_then(|value| base_uri.join(value.as_str()).ok())
-							.map(|value| value.to_string())
-					})
-			} else {
-				attr
-			}
-		} else {
-			attr
+		match name.strip_prefix("abs:") {
+			Some(name) => self.0.absolute_url(element.attr(name)?),
+			None => element.attr(name).map(|value| value.to_string()),
 		}
 	}
 
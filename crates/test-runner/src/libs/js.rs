@@ -0,0 +1,320 @@
+use boa_engine::{
+	Context, JsError, JsNativeError, JsResult, JsString, JsValue, NativeFunction, Source,
+	builtins::promise::PromiseState,
+	context::{ContextBuilder, time::FixedClock},
+	js_string,
+	object::{JsObject, ObjectInitializer},
+	property::Attribute,
+};
+use boa_gc::{Finalize, Gc, GcRefCell, Trace};
+use std::rc::Rc;
+
+/// The maximum number of timers that will be fired while waiting for a promise to settle.
+///
+/// This prevents scripts using `setInterval` from looping forever.
+const MAX_TIMER_TICKS: usize = 10_000;
+
+/// Browser globals (`window`, `document`, `atob`, `TextDecoder`, etc.) evaluated in every context.
+const PRELUDE: &str = include_str!("js_prelude.js");
+
+#[derive(Trace, Finalize)]
+struct Timer {
+	id: u32,
+	due: u64,
+	interval: Option<u64>,
+	callback: JsObject,
+	args: Vec<JsValue>,
+}
+
+#[derive(Trace, Finalize)]
+struct Timers {
+	now: u64,
+	next_id: u32,
+	pending: Vec<Timer>,
+}
+
+impl Timers {
+	fn add(&mut self, callback: JsObject, delay: u64, interval: bool, args: Vec<JsValue>) -> u32 {
+		self.next_id += 1;
+		self.pending.push(Timer {
+			id: self.next_id,
+			due: self.now + delay,
+			interval: interval.then_some(delay.max(1)),
+			callback,
+			args,
+		});
+		self.next_id
+	}
+
+	fn remove(&mut self, id: u32) {
+		self.pending.retain(|timer| timer.id != id);
+	}
+
+	/// Removes the timer that should fire next, rescheduling it if it's an interval.
+	fn pop(&mut self) -> Option<(u64, JsObject, Vec<JsValue>)> {
+		let idx = self
+			.pending
+			.iter()
+			.enumerate()
+			.min_by_key(|(_, timer)| (timer.due, timer.id))
+			.map(|(idx, _)| idx)?;
+		let due = self.pending[idx].due;
+		let result = if let Some(interval) = self.pending[idx].interval {
+			let timer = &mut self.pending[idx];
+			timer.due += interval;
+			(due, timer.callback.clone(), timer.args.clone())
+		} else {
+			let timer = self.pending.swap_remove(idx);
+			(due, timer.callback.clone(), timer.args.clone())
+		};
+		Some(result)
+	}
+}
+
+/// A JavaScript context with a fake clock and browser-like globals.
+///
+/// Time only advances when timers are fired while waiting for a promise to settle,
+/// so timers run instantly and `Date.now()` stays deterministic.
+///
+/// Output from `console` methods is buffered, and can be retrieved with [`JsContext::take_console_output`].
+pub struct JsContext {
+	pub context: Context,
+	clock: Rc<FixedClock>,
+	timers: Gc<GcRefCell<Timers>>,
+	console: Gc<GcRefCell<String>>,
+}
+
+impl JsContext {
+	pub fn new() -> Self {
+		Self::with_time(chrono::Utc::now().timestamp_millis().max(0) as u64)
+	}
+
+	/// Creates a context with the clock starting at the given unix time in milliseconds.
+	pub fn with_time(now: u64) -> Self {
+		let clock = Rc::new(FixedClock::from_millis(now));
+		let mut context = ContextBuilder::default()
+			.clock(clock.clone())
+			.build()
+			.expect("failed to build js context");
+		let timers = Gc::new(GcRefCell::new(Timers {
+			now,
+			next_id: 0,
+			pending: Vec::new(),
+		}));
+		let console = Gc::new(GcRefCell::new(String::new()));
+		Self::register_timers(&mut context, &timers);
+		Self::register_console(&mut context, &console);
+		context
+			.eval(Source::from_bytes(PRELUDE))
+			.expect("failed to evaluate js prelude");
+		Self {
+			context,
+			clock,
+			timers,
+			console,
+		}
+	}
+
+	/// Takes the output written by `console` methods since the last call.
+	pub fn take_console_output(&mut self) -> String {
+		std::mem::take(&mut *self.console.borrow_mut())
+	}
+
+	fn register_console(context: &mut Context, output: &Gc<GcRefCell<String>>) {
+		fn log(
+			args: &[JsValue],
+			output: &Gc<GcRefCell<String>>,
+			prefix: &str,
+			context: &mut Context,
+		) -> JsResult<JsValue> {
+			let mut line = String::from(prefix);
+			for (idx, arg) in args.iter().enumerate() {
+				if idx > 0 {
+					line.push(' ');
+				}
+				if let Some(string) = arg.as_string() {
+					line.push_str(&string.to_std_string_escaped());
+				} else if arg.is_object() && !arg.is_callable() {
+					// objects are formatted as json where possible, like the app's console
+					let json = arg
+						.to_json(context)
+						.ok()
+						.flatten()
+						.map(|json| json.to_string());
+					match json {
+						Some(json) => line.push_str(&json),
+						None => line.push_str(&arg.display().to_string()),
+					}
+				} else {
+					line.push_str(&arg.display().to_string());
+				}
+			}
+			line.push('\n');
+			output.borrow_mut().push_str(&line);
+			Ok(JsValue::undefined())
+		}
+
+		let methods: [(JsString, &'static str); 5] = [
+			(js_string!("log"), ""),
+			(js_string!("info"), ""),
+			(js_string!("debug"), ""),
+			(js_string!("warn"), "[warn] "),
+			(js_string!("error"), "[error] "),
+		];
+		let mut console = ObjectInitializer::new(context);
+		for (name, prefix) in methods {
+			let function = NativeFunction::from_copy_closure_with_captures(
+				|_, args, (output, prefix), context| log(args, output, prefix, context),
+				(output.clone(), String::from(prefix)),
+			);
+			console.function(function, name, 0);
+		}
+		let console = console.build();
+		context
+			.register_global_property(js_string!("console"), console, Attribute::all())
+			.expect("failed to register console");
+	}
+
+	fn register_timers(context: &mut Context, timers: &Gc<GcRefCell<Timers>>) {
+		fn set_timer(
+			args: &[JsValue],
+			timers: &Gc<GcRefCell<Timers>>,
+			interval: bool,
+			context: &mut Context,
+		) -> JsResult<JsValue> {
+			let Some(callback) = args.first().and_then(JsValue::as_callable) else {
+				return Err(JsNativeError::typ()
+					.with_message("timer callback must be a function")
+					.into());
+			};
+			let delay = match args.get(1) {
+				Some(delay) => delay.to_number(context)?,
+				None => 0.0,
+			};
+			let delay = if delay.is_finite() && delay > 0.0 {
+				delay as u64
+			} else {
+				0
+			};
+			let extra = args.iter().skip(2).cloned().collect();
+			let id = timers.borrow_mut().add(callback, delay, interval, extra);
+			Ok(JsValue::new(id))
+		}
+		fn clear_timer(
+			args: &[JsValue],
+			timers: &Gc<GcRefCell<Timers>>,
+			context: &mut Context,
+		) -> JsResult<JsValue> {
+			if let Some(id) = args.first().filter(|id| !id.is_undefined()) {
+				let id = id.to_u32(context)?;
+				timers.borrow_mut().remove(id);
+			}
+			Ok(JsValue::undefined())
+		}
+
+		let functions: [(JsString, NativeFunction); 4] = [
+			(
+				js_string!("setTimeout"),
+				NativeFunction::from_copy_closure_with_captures(
+					|_, args, timers, context| set_timer(args, timers, false, context),
+					timers.clone(),
+				),
+			),
+			(
+				js_string!("setInterval"),
+				NativeFunction::from_copy_closure_with_captures(
+					|_, args, timers, context| set_timer(args, timers, true, context),
+					timers.clone(),
+				),
+			),
+			(
+				js_string!("clearTimeout"),
+				NativeFunction::from_copy_closure_with_captures(
+					|_, args, timers, context| clear_timer(args, timers, context),
+					timers.clone(),
+				),
+			),
+			(
+				js_string!("clearInterval"),
+				NativeFunction::from_copy_closure_with_captures(
+					|_, args, timers, context| clear_timer(args, timers, context),
+					timers.clone(),
+				),
+			),
+		];
+		for (name, function) in functions {
+			context
+				.register_global_builtin_callable(name, 1, function)
+				.expect("failed to register timer function");
+		}
+	}
+
+	pub fn eval(&mut self, source: &str) -> JsResult<JsValue> {
+		self.context.eval(Source::from_bytes(source))
+	}
+
+	/// Evaluates the given source, waiting for the result to settle if it's a promise.
+	///
+	/// The job queue is run to completion, and pending timers are fired in order
+	/// (advancing the fake clock) until the promise is fulfilled or rejected.
+	pub fn eval_async(&mut self, source: &str) -> JsResult<JsValue> {
+		let result = self.context.eval(Source::from_bytes(source))?;
+		self.context.run_jobs()?;
+		let Some(promise) = result.as_promise() else {
+			return Ok(result);
+		};
+		for _ in 0..MAX_TIMER_TICKS {
+			match promise.state() {
+				PromiseState::Fulfilled(value) => return Ok(value),
+				PromiseState::Rejected(reason) => return Err(JsError::from_opaque(reason)),
+				PromiseState::Pending => {
+					if !self.fire_next_timer()? {
+						break;
+					}
+					self.context.run_jobs()?;
+				}
+			}
+		}
+		match promise.state() {
+			PromiseState::Fulfilled(value) => Ok(value),
+			PromiseState::Rejected(reason) => Err(JsError::from_opaque(reason)),
+			PromiseState::Pending => Err(JsNativeError::error()
+				.with_message("promise never settled")
+				.into()),
+		}
+	}
+
+	pub fn get(&mut self, key: &str) -> JsResult<JsValue> {
+		let key: JsString = key.into();
+		self.context.global_object().get(key, &mut self.context)
+	}
+
+	pub fn to_string(&mut self, value: &JsValue) -> Option<String> {
+		value
+			.to_string(&mut self.context)
+			.ok()
+			.and_then(|s| s.to_std_string().ok())
+	}
+
+	/// Fires the next pending timer, returning false if there are none.
+	fn fire_next_timer(&mut self) -> JsResult<bool> {
+		let Some((due, callback, args)) = self.timers.borrow_mut().pop() else {
+			return Ok(false);
+		};
+		{
+			let mut timers = self.timers.borrow_mut();
+			if due > timers.now {
+				self.clock.forward(due - timers.now);
+				timers.now = due;
+			}
+		}
+		callback.call(&JsValue::undefined(), &args, &mut self.context)?;
+		Ok(true)
+	}
+}
+
+impl Default for JsContext {
+	fn default() -> Self {
+		Self::new()
+	}
+}
@@ -0,0 +1,200 @@
+// Browser-like globals, matching the ones available in the app's JavaScript context.
+var window = globalThis;
+var self = globalThis;
+
+var navigator = {
+	userAgent:
+		"Mozilla/5.0 (iPhone; CPU iPhone OS 18_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Mobile/15E148",
+	language: "en-US",
+	languages: ["en-US", "en"],
+	platform: "iPhone",
+	cookieEnabled: true,
+};
+
+var location = {
+	href: "about:blank",
+	protocol: "about:",
+	host: "",
+	hostname: "",
+	origin: "null",
+	pathname: "blank",
+	search: "",
+	hash: "",
+	toString() {
+		return this.href;
+	},
+};
+
+var document = {
+	cookie: "",
+	referrer: "",
+	location: location,
+	documentElement: {},
+	head: {},
+	body: {},
+	getElementById() {
+		return null;
+	},
+	getElementsByTagName() {
+		return [];
+	},
+	getElementsByClassName() {
+		return [];
+	},
+	querySelector() {
+		return null;
+	},
+	querySelectorAll() {
+		return [];
+	},
+	createElement(tagName) {
+		return { tagName: String(tagName).toUpperCase(), style: {}, setAttribute() {}, appendChild() {} };
+	},
+	addEventListener() {},
+	removeEventListener() {},
+};
+
+window.addEventListener = function () {};
+window.removeEventListener = function () {};
+
+(function () {
+	var chars = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+	window.btoa = function (input) {
+		var str = String(input);
+		var output = "";
+		for (var i = 0; i < str.length; i += 3) {
+			var a = str.charCodeAt(i);
+			var b = str.charCodeAt(i + 1);
+			var c = str.charCodeAt(i + 2);
+			if (a > 255 || b > 255 || c > 255) {
+				throw new Error("btoa: string contains characters outside of the Latin1 range");
+			}
+			var triplet = (a << 16) | ((b || 0) << 8) | (c || 0);
+			output += chars.charAt((triplet >> 18) & 63) + chars.charAt((triplet >> 12) & 63);
+			output += i + 1 < str.length ? chars.charAt((triplet >> 6) & 63) : "=";
+			output += i + 2 < str.length ? chars.charAt(triplet & 63) : "=";
+		}
+		return output;
+	};
+
+	window.atob = function (input) {
+		var str = String(input).replace(/[\t\n\f\r ]/g, "").replace(/=+$/, "");
+		if (str.length % 4 == 1 || /[^A-Za-z0-9+/]/.test(str)) {
+			throw new Error("atob: the string to be decoded is not correctly encoded");
+		}
+		var output = "";
+		var buffer = 0;
+		var bits = 0;
+		for (var i = 0; i < str.length; i++) {
+			buffer = (buffer << 6) | chars.indexOf(str.charAt(i));
+			bits += 6;
+			if (bits >= 8) {
+				bits -= 8;
+				output += String.fromCharCode((buffer >> bits) & 255);
+			}
+		}
+		return output;
+	};
+
+	function TextEncoder() {}
+	TextEncoder.prototype.encoding = "utf-8";
+	TextEncoder.prototype.encode = function (input) {
+		var str = input === undefined ? "" : String(input);
+		var bytes = [];
+		for (var i = 0; i < str.length; i++) {
+			var code = str.codePointAt(i);
+			if (code > 0xffff) {
+				i++;
+			}
+			if (code >= 0xd800 && code <= 0xdfff) {
+				code = 0xfffd;
+			}
+			if (code < 0x80) {
+				bytes.push(code);
+			} else if (code < 0x800) {
+				bytes.push(0xc0 | (code >> 6), 0x80 | (code & 63));
+			} else if (code < 0x10000) {
+				bytes.push(0xe0 | (code >> 12), 0x80 | ((code >> 6) & 63), 0x80 | (code & 63));
+			} else {
+				bytes.push(
+					0xf0 | (code >> 18),
+					0x80 | ((code >> 12) & 63),
+					0x80 | ((code >> 6) & 63),
+					0x80 | (code & 63)
+				);
+			}
+		}
+		return new Uint8Array(bytes);
+	};
+
+	function TextDecoder(label) {
+		var encoding = label === undefined ? "utf-8" : String(label).toLowerCase();
+		if (encoding == "utf8") {
+			encoding = "utf-8";
+		}
+		if (encoding != "utf-8" && encoding != "latin1" && encoding != "iso-8859-1") {
+			throw new RangeError("TextDecoder: unsupported encoding " + label);
+		}
+		this.encoding = encoding;
+	}
+	TextDecoder.prototype.decode = function (input) {
+		if (input === undefined) {
+			return "";
+		}
+		var bytes;
+		if (ArrayBuffer.isView(input)) {
+			// only decode the bytes in the view, not its whole buffer
+			bytes = new Uint8Array(input.buffer, input.byteOffset, input.byteLength);
+		} else {
+			bytes = new Uint8Array(input);
+		}
+		var output = "";
+		if (this.encoding != "utf-8") {
+			for (var j = 0; j < bytes.length; j++) {
+				output += String.fromCharCode(bytes[j]);
+			}
+			return output;
+		}
+		var i = 0;
+		while (i < bytes.length) {
+			var byte = bytes[i++];
+			var code;
+			var extra;
+			if (byte < 0x80) {
+				code = byte;
+				extra = 0;
+			} else if (byte >= 0xc2 && byte < 0xe0) {
+				code = byte & 31;
+				extra = 1;
+			} else if (byte >= 0xe0 && byte < 0xf0) {
+				code = byte & 15;
+				extra = 2;
+			} else if (byte >= 0xf0 && byte < 0xf5) {
+				code = byte & 7;
+				extra = 3;
+			} else {
+				output += "�";
+				continue;
+			}
+			var valid = i + extra <= bytes.length;
+			for (var k = 0; valid && k < extra; k++) {
+				if ((bytes[i + k] & 0xc0) != 0x80) {
+					valid = false;
+				}
+			}
+			if (!valid) {
+				output += "�";
+				continue;
+			}
+			for (var n = 0; n < extra; n++) {
+				code = (code << 6) | (bytes[i++] & 63);
+			}
+			output += String.fromCodePoint(code);
+		}
+		return output;
+	};
+
+	window.TextEncoder = TextEncoder;
+	window.TextDecoder = TextDecoder;
+})();
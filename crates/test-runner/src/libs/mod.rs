@@ -1,14 +1,24 @@
+use crate::{config::Config, profile::Profile};
 use anyhow::{Result, anyhow};
+use std::{cell::Cell, collections::HashMap, sync::Arc};
 use wasmer::*;
 
+mod cookies;
 mod defaults;
 mod html;
+mod js;
 mod net;
+mod rate_limit;
+mod snapshot;
 mod store;
 
+pub use cookies::*;
 pub use defaults::*;
 pub use html::*;
+pub use js::*;
 pub use net::*;
+pub use rate_limit::*;
+pub use snapshot::*;
 pub use store::*;
 
 /// A standard descriptor, used for data exchange between the runner and the source (reference id).
@@ -30,7 +40,20 @@ pub struct WasmEnv {
 	pub memory: Option<Memory>,
 	pub store: GlobalStore,
 	pub defaults: UserDefaults,
+	/// The cookies and rate limits of all requests.
+	pub net: Arc<NetState>,
+	/// Requests that are being sent in the background.
+	pub pending: HashMap<Rid, PendingSend>,
 	pub stdout: String,
+	pub config: Arc<Config>,
+	/// The name of the test being run.
+	pub test_name: Option<String>,
+	/// Import statistics, if profiling is enabled.
+	pub profile: Option<Profile>,
+	/// The total number of bytes read from wasm memory.
+	pub bytes_read: Cell<u64>,
+	/// The total number of bytes written to wasm memory.
+	pub bytes_written: Cell<u64>,
 }
 
 impl WasmEnv {
@@ -39,10 +62,26 @@ impl WasmEnv {
 			memory: None,
 			store: GlobalStore::new(),
 			defaults: UserDefaults::new(),
+			net: Arc::default(),
+			pending: HashMap::new(),
 			stdout: String::new(),
+			config: Arc::default(),
+			test_name: None,
+			profile: None,
+			bytes_read: Cell::new(0),
+			bytes_written: Cell::new(0),
 		}
 	}
 
+	/// Creates an environment using the given configuration.
+	pub fn with_config(config: Arc<Config>) -> Result<Self> {
+		Ok(Self {
+			defaults: config.user_defaults()?,
+			config,
+			..Self::new()
+		})
+	}
+
 	pub fn read_u32(&self, store: &(impl AsStoreRef + ?Sized), ptr: Ptr) -> Result<u32> {
 		let memory = self
 			.memory
@@ -51,6 +90,7 @@ impl WasmEnv {
 		let memory_view = memory.view(&store);
 		let ptr: WasmPtr<u32> = WasmPtr::new(ptr);
 		let value = ptr.read(&memory_view).unwrap_or(0);
+		self.add_bytes_read(4);
 		Ok(value)
 	}
 
@@ -75,6 +115,7 @@ impl WasmEnv {
 		for byte in bytes.iter() {
 			data.push(byte.read()?);
 		}
+		self.add_bytes_read(bytes.len() * size_of::<T>() as u64);
 		Ok(data)
 	}
 
@@ -99,6 +140,7 @@ impl WasmEnv {
 		for byte in bytes.iter() {
 			data.push(byte.read()?);
 		}
+		self.add_bytes_read(len as u64);
 		Ok(data)
 	}
 
@@ -123,6 +165,7 @@ impl WasmEnv {
 		let memory_view = memory.view(&store);
 		let ptr: WasmPtr<u8> = WasmPtr::new(ptr);
 		let str = ptr.read_utf8_string(&memory_view, len)?;
+		self.add_bytes_read(len as u64);
 		Ok(str)
 	}
 
@@ -147,6 +190,7 @@ impl WasmEnv {
 			.ok_or(anyhow!("Memory not initialized"))?;
 		let memory_view = memory.view(&store);
 		memory_view.write(ptr as u64, bytes)?;
+		self.add_bytes_written(bytes.len() as u64);
 		Ok(())
 	}
 
@@ -167,10 +211,19 @@ impl WasmEnv {
 		for (idx, val) in values.into_iter().enumerate() {
 			buffer.write(idx as u64, val)?;
 		}
+		self.add_bytes_written(buffer.len() * size_of::<T>() as u64);
 		Ok(())
 	}
 
 	pub fn write_stdout(&mut self, str: &str) {
 		self.stdout.push_str(str);
 	}
+
+	fn add_bytes_read(&self, len: u64) {
+		self.bytes_read.set(self.bytes_read.get() + len);
+	}
+
+	fn add_bytes_written(&self, len: u64) {
+		self.bytes_written.set(self.bytes_written.get() + len);
+	}
 }
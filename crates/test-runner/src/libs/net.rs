@@ -1,6 +1,32 @@
-use reqwest::{StatusCode, header::HeaderMap};
+use super::{CookieStore, RateLimits};
+use crate::config::Config;
+use reqwest::{
+	StatusCode,
+	header::{
+		CONTENT_RANGE, COOKIE, ETAG, HeaderMap, HeaderName, HeaderValue, IF_NONE_MATCH, RANGE,
+		USER_AGENT,
+	},
+	redirect::Policy,
+};
+use std::{
+	hash::{DefaultHasher, Hash, Hasher},
+	sync::{Arc, Mutex},
+	thread::JoinHandle,
+};
 use url::Url;
 
+/// Returns the `ETag` of a fixture, a hash of its content.
+pub fn fixture_etag(data: &[u8]) -> String {
+	let mut hasher = DefaultHasher::new();
+	data.hash(&mut hasher);
+	format!("\"{:016x}\"", hasher.finish())
+}
+
+/// The number of redirects followed when the request doesn't set a limit.
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+pub const DEFAULT_USER_AGENT: &str = "Aidoku/1 CFNetwork/3826.500.131 Darwin/24.5.0";
+
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum HttpMethod {
 	Get,
@@ -14,24 +40,38 @@ pub enum HttpMethod {
 	Trace,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NetResponse {
 	pub url: Url,
 	pub status: StatusCode,
 	pub headers: HeaderMap,
 	pub data: Vec<u8>,
+	/// The urls that the request was redirected to, in order.
+	pub redirects: Vec<Url>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NetRequest {
 	pub method: HttpMethod,
 	pub url: Option<Url>,
 	pub headers: HeaderMap,
 	pub body: Option<Vec<u8>>,
 	pub timeout: Option<f64>,
+	/// The maximum number of redirects to follow, or none to follow up to the default limit.
+	pub max_redirects: Option<usize>,
 	pub response: Option<NetResponse>,
 }
 
+/// State shared by all requests of a source, including ones sent in the background.
+#[derive(Debug, Default)]
+pub struct NetState {
+	pub cookies: Mutex<CookieStore>,
+	pub rate_limits: Mutex<RateLimits>,
+}
+
+/// A request being sent on another thread, which returns the response and send result.
+pub type PendingSend = JoinHandle<(Option<NetResponse>, Result<(), SendError>)>;
+
 impl NetRequest {
 	pub fn new(method: HttpMethod) -> Self {
 		Self {
@@ -40,7 +80,245 @@ impl NetRequest {
 			headers: HeaderMap::new(),
 			body: None,
 			timeout: None,
+			max_redirects: None,
 			response: None,
 		}
 	}
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendError {
+	InvalidUrl,
+	RequestError,
+	/// The request was blocked by the network policy.
+	Blocked,
+}
+
+impl NetRequest {
+	/// Creates a response from a fixture, serving conditional and range requests like a
+	/// server would.
+	///
+	/// Fixtures have an `ETag` of a hash of their content, so requests with a matching
+	/// `If-None-Match` header get a `304 Not Modified` response.
+	fn fixture_response(&self, url: Url, data: Vec<u8>) -> NetResponse {
+		let mut status = StatusCode::OK;
+		let mut headers = HeaderMap::new();
+		let mut data = data;
+		let etag = fixture_etag(&data);
+		if let Ok(value) = HeaderValue::from_str(&etag) {
+			headers.insert(ETAG, value);
+		}
+		if self
+			.headers
+			.get(IF_NONE_MATCH)
+			.and_then(|value| value.to_str().ok())
+			.is_some_and(|value| {
+				value
+					.split(',')
+					.any(|tag| tag.trim() == "*" || tag.trim() == etag)
+			}) {
+			status = StatusCode::NOT_MODIFIED;
+			data = Vec::new();
+		} else if let Some((start, end)) = self
+			.headers
+			.get(RANGE)
+			.and_then(|value| value.to_str().ok())
+			.and_then(|value| parse_range(value, data.len() as u64))
+		{
+			let content_range = format!("bytes {start}-{end}/{}", data.len());
+			if let Ok(value) = HeaderValue::from_str(&content_range) {
+				headers.insert(CONTENT_RANGE, value);
+			}
+			status = StatusCode::PARTIAL_CONTENT;
+			data = data[start as usize..=end as usize].to_vec();
+		}
+		NetResponse {
+			url,
+			status,
+			headers,
+			data,
+			redirects: Vec::new(),
+		}
+	}
+
+	/// Sends the request, storing the response in the request.
+	///
+	/// Responses are read from the configured fixtures directory if possible. Cookies are
+	/// sent from and stored in the shared cookie store, and requests that aren't served from
+	/// fixtures wait for the shared rate limits.
+	pub fn send(&mut self, config: &Config, state: &NetState) -> Result<(), SendError> {
+		let Some(url) = self.url.as_ref() else {
+			return Err(SendError::InvalidUrl);
+		};
+		if let Some(data) = config.fixture(self.method, url) {
+			self.response = Some(self.fixture_response(url.clone(), data));
+			return Ok(());
+		}
+		if !config.net.allows(url) {
+			return Err(SendError::Blocked);
+		}
+		let delay = state
+			.rate_limits
+			.lock()
+			.unwrap()
+			.reserve(url, std::time::Instant::now());
+		if !delay.is_zero() {
+			std::thread::sleep(delay);
+		}
+
+		// add a default user agent if none is provided
+		if !self.headers.contains_key(USER_AGENT) {
+			let user_agent = match &config.net.user_agent {
+				Some(user_agent) => HeaderValue::from_str(user_agent),
+				None => Ok(HeaderValue::from_static(DEFAULT_USER_AGENT)),
+			};
+			if let Ok(user_agent) = user_agent {
+				self.headers.insert(USER_AGENT, user_agent);
+			}
+		}
+		for (name, value) in &config.net.headers {
+			if let (Ok(name), Ok(value)) = (
+				HeaderName::from_bytes(name.as_bytes()),
+				HeaderValue::from_str(value),
+			) && !self.headers.contains_key(&name)
+			{
+				self.headers.insert(name, value);
+			}
+		}
+
+		let redirects = Arc::new(Mutex::new(Vec::new()));
+		let mut client = reqwest::blocking::Client::builder()
+			.redirect(self.redirect_policy(config, redirects.clone()));
+		if let Some(proxy) = &config.net.proxy {
+			let proxy = reqwest::Proxy::all(proxy).map_err(|_| SendError::RequestError)?;
+			client = client.proxy(proxy);
+		}
+		let client = client.build().map_err(|_| SendError::RequestError)?;
+		let now = config.now().timestamp();
+		let mut headers = self.headers.clone();
+		if !headers.contains_key(COOKIE)
+			&& let Some(value) = state
+				.cookies
+				.lock()
+				.unwrap()
+				.header(url, now)
+				.and_then(|value| HeaderValue::from_str(&value).ok())
+		{
+			headers.insert(COOKIE, value);
+		}
+		let mut builder = client
+			.request(
+				match self.method {
+					HttpMethod::Get => reqwest::Method::GET,
+					HttpMethod::Post => reqwest::Method::POST,
+					HttpMethod::Put => reqwest::Method::PUT,
+					HttpMethod::Delete => reqwest::Method::DELETE,
+					HttpMethod::Head => reqwest::Method::HEAD,
+					HttpMethod::Patch => reqwest::Method::PATCH,
+					HttpMethod::Options => reqwest::Method::OPTIONS,
+					HttpMethod::Connect => reqwest::Method::CONNECT,
+					HttpMethod::Trace => reqwest::Method::TRACE,
+				},
+				url.to_string(),
+			)
+			.headers(headers);
+		// the body and timeout are kept, so the request can be sent again when retrying
+		if let Some(body) = self.body.clone() {
+			builder = builder.body(body);
+		}
+		if let Some(timeout) = self.timeout.or(config.net.timeout) {
+			let secs = timeout.trunc() as u64;
+			let nanos = ((timeout.fract()) * 1_000_000_000.0).round() as u32;
+			builder = builder.timeout(std::time::Duration::new(secs, nanos));
+		}
+		// make a blocking request with reqwest
+		let response = builder.send().map_err(|_| {
+			// a redirect that the network policy blocks is recorded before failing
+			match redirects.lock().unwrap().last() {
+				Some(url) if !config.net.allows(url) => SendError::Blocked,
+				_ => SendError::RequestError,
+			}
+		})?;
+		let url = response.url().clone();
+		let status = response.status();
+		let headers = response.headers().clone();
+		state
+			.cookies
+			.lock()
+			.unwrap()
+			.store_response(&url, &headers, now);
+		let bytes = response.bytes().map_err(|_| SendError::RequestError)?;
+		self.response = Some(NetResponse {
+			url,
+			status,
+			headers,
+			data: bytes.into(),
+			redirects: std::mem::take(&mut redirects.lock().unwrap()),
+		});
+		Ok(())
+	}
+}
+
+impl NetRequest {
+	/// Returns the redirect policy for the request, which records the followed redirects.
+	///
+	/// When following redirects up to the default limit, exceeding it is an error like it
+	/// is in reqwest. An explicit limit returns the last redirect response instead.
+	/// Redirects to hosts that the network policy blocks are an error.
+	fn redirect_policy(&self, config: &Config, redirects: Arc<Mutex<Vec<Url>>>) -> Policy {
+		let max_redirects = self.max_redirects;
+		let net = config.net.clone();
+		Policy::custom(move |attempt| {
+			if !net.allows(attempt.url()) {
+				redirects.lock().unwrap().push(attempt.url().clone());
+				return attempt.error("blocked by network policy");
+			}
+			let hops = attempt.previous().len();
+			match max_redirects {
+				None if hops > DEFAULT_MAX_REDIRECTS => attempt.error("too many redirects"),
+				Some(max) if hops > max => attempt.stop(),
+				_ => {
+					redirects.lock().unwrap().push(attempt.url().clone());
+					attempt.follow()
+				}
+			}
+		})
+	}
+
+	/// Starts sending a copy of the request on another thread.
+	pub fn send_in_background(&self, config: Arc<Config>, state: Arc<NetState>) -> PendingSend {
+		let mut request = self.clone();
+		std::thread::spawn(move || {
+			let result = request.send(&config, &state);
+			(request.response, result)
+		})
+	}
+}
+
+/// Parses a `Range` header value with a single range, like `bytes=0-99`, `bytes=100-` or
+/// `bytes=-100`, returning the inclusive start and end offsets within a body of the given
+/// length.
+pub fn parse_range(value: &str, len: u64) -> Option<(u64, u64)> {
+	let (start, end) = value.trim().strip_prefix("bytes=")?.split_once('-')?;
+	let (start, end) = (start.trim(), end.trim());
+	if len == 0 {
+		return None;
+	}
+	let (start, end) = if start.is_empty() {
+		// the last bytes of the body
+		let suffix: u64 = end.parse().ok()?;
+		if suffix == 0 {
+			return None;
+		}
+		(len.saturating_sub(suffix), len - 1)
+	} else {
+		let start: u64 = start.parse().ok()?;
+		let end = if end.is_empty() {
+			len - 1
+		} else {
+			end.parse::<u64>().ok()?.min(len - 1)
+		};
+		(start, end)
+	};
+	(start <= end).then_some((start, end))
+}
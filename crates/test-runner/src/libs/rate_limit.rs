@@ -0,0 +1,148 @@
+use std::{
+	collections::VecDeque,
+	time::{Duration, Instant},
+};
+use url::Url;
+
+/// A limit of a number of requests per period.
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+	permits: usize,
+	period: Duration,
+	/// The times of the most recent requests, oldest first.
+	sent: VecDeque<Instant>,
+}
+
+impl RateLimit {
+	pub fn new(permits: usize, period: Duration) -> Self {
+		Self {
+			permits,
+			period,
+			sent: VecDeque::new(),
+		}
+	}
+
+	/// Creates a limit from the values passed to the `set_rate_limit` imports.
+	///
+	/// Returns `None` if there are no permits, the period is negative, or the unit is unknown.
+	pub fn from_ffi(permits: i32, period: i32, unit: i32) -> Option<Self> {
+		let seconds = match unit {
+			0 => 1,
+			1 => 60,
+			2 => 60 * 60,
+			_ => return None,
+		};
+		let permits = usize::try_from(permits)
+			.ok()
+			.filter(|permits| *permits > 0)?;
+		let period = u64::try_from(period).ok()?;
+		Some(Self::new(permits, Duration::from_secs(period * seconds)))
+	}
+
+	/// Returns how long a request at the given time has to wait.
+	pub fn delay(&self, now: Instant) -> Duration {
+		if self.sent.len() < self.permits {
+			return Duration::ZERO;
+		}
+		let ready = self.sent[self.sent.len() - self.permits] + self.period;
+		ready.saturating_duration_since(now)
+	}
+
+	/// Records a request sent at the given time.
+	pub fn record(&mut self, at: Instant) {
+		self.sent.push_back(at);
+		while self.sent.len() > self.permits {
+			self.sent.pop_front();
+		}
+	}
+}
+
+/// The rate limits of a source, both global and for specific hosts.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimits {
+	global: Option<RateLimit>,
+	/// Limits keyed by host or url pattern.
+	patterns: Vec<(String, RateLimit)>,
+}
+
+impl RateLimits {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the limit for all requests.
+	pub fn set_global(&mut self, limit: Option<RateLimit>) {
+		self.global = limit;
+	}
+
+	/// Sets the limit for requests matching a pattern.
+	///
+	/// A pattern is either a host, which may start with `*.` to match subdomains, or a
+	/// url prefix like `https://example.com/api/`.
+	pub fn set(&mut self, pattern: &str, limit: Option<RateLimit>) {
+		let pattern = normalize_pattern(pattern);
+		self.patterns.retain(|(existing, _)| *existing != pattern);
+		if let Some(limit) = limit {
+			self.patterns.push((pattern, limit));
+		}
+	}
+
+	/// Returns the index of the most specific pattern matching the url.
+	fn matching_pattern(&self, url: &Url) -> Option<usize> {
+		let host = url.host_str()?.to_ascii_lowercase();
+		self.patterns
+			.iter()
+			.enumerate()
+			.filter(|(_, (pattern, _))| pattern_matches(pattern, &host, url))
+			.max_by_key(|(_, (pattern, _))| pattern.len())
+			.map(|(idx, _)| idx)
+	}
+
+	/// Reserves a request to the url at the given time, returning how long it has to wait
+	/// before being sent.
+	pub fn reserve(&mut self, url: &Url, now: Instant) -> Duration {
+		let pattern = self.matching_pattern(url);
+		let delay = self
+			.global
+			.iter()
+			.chain(pattern.map(|idx| &self.patterns[idx].1))
+			.map(|limit| limit.delay(now))
+			.max()
+			.unwrap_or_default();
+		let at = now + delay;
+		if let Some(limit) = self.global.as_mut() {
+			limit.record(at);
+		}
+		if let Some(idx) = pattern {
+			self.patterns[idx].1.record(at);
+		}
+		delay
+	}
+}
+
+/// Lowercases a pattern, except for the path of url prefixes, which is case sensitive.
+fn normalize_pattern(pattern: &str) -> String {
+	let pattern = pattern.trim();
+	let Some(scheme_end) = pattern.find("://") else {
+		return pattern.to_ascii_lowercase();
+	};
+	let host_end = pattern[scheme_end + 3..]
+		.find(['/', '?', '#'])
+		.map_or(pattern.len(), |idx| scheme_end + 3 + idx);
+	let (origin, path) = pattern.split_at(host_end);
+	format!("{}{path}", origin.to_ascii_lowercase())
+}
+
+fn pattern_matches(pattern: &str, host: &str, url: &Url) -> bool {
+	if pattern.contains("://") {
+		// the scheme and host of the url are already lowercase
+		url.as_str().starts_with(pattern)
+	} else if let Some(domain) = pattern.strip_prefix("*.") {
+		host == domain
+			|| host
+				.strip_suffix(domain)
+				.is_some_and(|prefix| prefix.ends_with('.'))
+	} else {
+		host == pattern
+	}
+}
@@ -0,0 +1,92 @@
+use crate::config::Config;
+use serde_json::Value;
+use similar::TextDiff;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that causes mismatched snapshots to be overwritten when set.
+pub const UPDATE_SNAPSHOTS_VAR: &str = "AIDOKU_UPDATE_SNAPSHOTS";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SnapshotResult {
+	/// The snapshot matched the stored file.
+	Matched,
+	/// No snapshot existed, so a new one was written.
+	Created,
+	/// The snapshot didn't match, and the stored file was overwritten.
+	Updated,
+	/// The snapshot didn't match. Contains a diff of the stored and new snapshots.
+	Mismatched(String),
+}
+
+/// A directory of snapshot files.
+#[derive(Debug, Clone)]
+pub struct Snapshots {
+	pub dir: PathBuf,
+	/// Whether mismatched snapshots should be overwritten.
+	pub update: bool,
+}
+
+impl Snapshots {
+	pub fn new(dir: impl Into<PathBuf>) -> Self {
+		Self {
+			dir: dir.into(),
+			update: false,
+		}
+	}
+
+	/// Returns the configured snapshots directory, using the update flag from the environment.
+	pub fn from_config(config: &Config) -> Self {
+		Self {
+			dir: config.snapshots_dir(),
+			update: std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some(),
+		}
+	}
+
+	/// Returns the path of the snapshot with the given name for a test.
+	pub fn path(&self, test_name: &str, name: &str) -> PathBuf {
+		let file_name = format!("{test_name}__{name}.json")
+			.replace("::", "__")
+			.replace(['/', '\\'], "_");
+		self.dir.join(file_name)
+	}
+
+	/// Compares a value with the stored snapshot, creating or updating the file if needed.
+	pub fn assert(
+		&self,
+		test_name: &str,
+		name: &str,
+		value: &Value,
+	) -> std::io::Result<SnapshotResult> {
+		let path = self.path(test_name, name);
+		let mut snapshot = serde_json::to_string_pretty(value)?;
+		snapshot.push('\n');
+
+		let result = match std::fs::read_to_string(&path) {
+			Ok(stored) if stored == snapshot => return Ok(SnapshotResult::Matched),
+			Ok(stored) if !self.update => {
+				return Ok(SnapshotResult::Mismatched(diff(&stored, &snapshot)));
+			}
+			Ok(_) => SnapshotResult::Updated,
+			Err(err) if err.kind() == std::io::ErrorKind::NotFound => SnapshotResult::Created,
+			Err(err) => return Err(err),
+		};
+		write(&path, &snapshot)?;
+		Ok(result)
+	}
+}
+
+fn write(path: &Path, snapshot: &str) -> std::io::Result<()> {
+	if let Some(parent) = path.parent() {
+		std::fs::create_dir_all(parent)?;
+	}
+	std::fs::write(path, snapshot)
+}
+
+/// Returns a unified diff of the stored and new snapshots.
+fn diff(old: &str, new: &str) -> String {
+	TextDiff::from_lines(old, new)
+		.unified_diff()
+		.context_radius(3)
+		.header("stored", "new")
+		.to_string()
+}
@@ -1,5 +1,4 @@
-use super::{HtmlDocument, HtmlElement, HtmlElementList, HtmlNode, NetRequest, Rid};
-use boa_engine::Context;
+use super::{HtmlDocument, HtmlElement, HtmlElementList, HtmlNode, JsContext, NetRequest, Rid};
 use font_kit::font::Font;
 use raqote::DrawTarget;
 use serde::Serialize;
@@ -19,7 +18,7 @@ pub enum StoreItem {
 	HtmlNode(HtmlNode),
 	HtmlNodeList(Vec<HtmlNode>),
 	HtmlElementList(HtmlElementList),
-	JsContext(Box<Context>),
+	JsContext(Box<JsContext>),
 	Encoded(Vec<u8>),
 	Canvas(Box<DrawTarget>),
 	Font(Font),
@@ -81,7 +80,7 @@ impl StoreItem {
 		}
 	}
 
-	pub fn as_js_context(&mut self) -> Option<&mut Context> {
+	pub fn as_js_context(&mut self) -> Option<&mut JsContext> {
 		if let StoreItem::JsContext(c) = self {
 			Some(c)
 		} else {
@@ -0,0 +1,128 @@
+//! Profiling of host import calls.
+//!
+//! When enabled, every import is wrapped so that the number of calls, the time spent in the
+//! host, and the bytes copied across the wasm boundary are recorded per import.
+use crate::WasmEnv;
+use std::{collections::HashMap, fmt, time::Duration, time::Instant};
+use wasmer::{AsStoreMut, Extern, Function, FunctionEnv, FunctionEnvMut, Imports};
+
+/// Environment variable that enables profiling when set.
+pub const PROFILE_VAR: &str = "AIDOKU_TEST_PROFILE";
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ImportStats {
+	pub calls: u64,
+	pub time: Duration,
+	/// Bytes read from wasm memory.
+	pub bytes_in: u64,
+	/// Bytes written to wasm memory.
+	pub bytes_out: u64,
+}
+
+/// Collected statistics for each import, keyed by `module::name`.
+#[derive(Debug, Default)]
+pub struct Profile {
+	pub imports: HashMap<String, ImportStats>,
+}
+
+impl Profile {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Returns the statistics of all imports combined.
+	pub fn total(&self) -> ImportStats {
+		self.imports
+			.values()
+			.fold(ImportStats::default(), |total, stats| ImportStats {
+				calls: total.calls + stats.calls,
+				time: total.time + stats.time,
+				bytes_in: total.bytes_in + stats.bytes_in,
+				bytes_out: total.bytes_out + stats.bytes_out,
+			})
+	}
+}
+
+impl fmt::Display for Profile {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let mut imports = self.imports.iter().collect::<Vec<_>>();
+		// sort by time spent, then by number of calls
+		imports.sort_by(|a, b| {
+			b.1.time
+				.cmp(&a.1.time)
+				.then(b.1.calls.cmp(&a.1.calls))
+				.then(a.0.cmp(b.0))
+		});
+		let width = imports
+			.iter()
+			.map(|(name, _)| name.len())
+			.max()
+			.unwrap_or(0)
+			.max(5);
+		writeln!(
+			f,
+			"{:<width$} {:>8} {:>12} {:>12} {:>12}",
+			"import", "calls", "time", "bytes in", "bytes out"
+		)?;
+		let total = self.total();
+		for (name, stats) in imports.into_iter().chain([(&"total".into(), &total)]) {
+			writeln!(
+				f,
+				"{:<width$} {:>8} {:>12} {:>12} {:>12}",
+				name,
+				stats.calls,
+				format!("{:.3?}", stats.time),
+				stats.bytes_in,
+				stats.bytes_out
+			)?;
+		}
+		Ok(())
+	}
+}
+
+/// Wraps every function in the given imports to record statistics in the environment's profile.
+pub fn profile_imports(
+	store: &mut impl AsStoreMut,
+	env: &FunctionEnv<WasmEnv>,
+	imports: &Imports,
+) -> Imports {
+	env.as_mut(store).profile = Some(Profile::new());
+
+	let mut profiled = Imports::new();
+	for ((module, name), import) in imports {
+		let Extern::Function(function) = import else {
+			profiled.define(&module, &name, import);
+			continue;
+		};
+		let key = format!("{module}::{name}");
+		let ty = function.ty(store);
+		let wrapper = Function::new_with_env(
+			store,
+			env,
+			ty,
+			move |mut env: FunctionEnvMut<WasmEnv>, args| {
+				let (read, written) = {
+					let data = env.data();
+					(data.bytes_read.get(), data.bytes_written.get())
+				};
+				let start = Instant::now();
+				let result = function.call(&mut env, args);
+				let time = start.elapsed();
+
+				let data = env.data_mut();
+				let bytes_in = data.bytes_read.get() - read;
+				let bytes_out = data.bytes_written.get() - written;
+				if let Some(profile) = data.profile.as_mut() {
+					let stats = profile.imports.entry(key.clone()).or_default();
+					stats.calls += 1;
+					stats.time += time;
+					stats.bytes_in += bytes_in;
+					stats.bytes_out += bytes_out;
+				}
+				result.map(|values| values.into_vec())
+			},
+		);
+		profiled.define(&module, &name, wrapper);
+	}
+	profiled
+}
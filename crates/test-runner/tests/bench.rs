@@ -0,0 +1,59 @@
+use aidoku_test_runner::bench::{BenchResult, Benchmarks, measure};
+use std::collections::BTreeMap;
+
+#[test]
+fn test_measure() {
+	let mut total = 0;
+	let result = measure(|iterations| {
+		for _ in 0..iterations {
+			std::thread::sleep(std::time::Duration::from_micros(50));
+		}
+		total += iterations as u64;
+		Ok(())
+	})
+	.unwrap();
+	// warmup batches are not counted
+	assert!(result.iterations < total);
+	assert_eq!(result.iterations % 50, 0);
+	assert!(result.mean >= 50_000.0);
+	assert!(result.variance >= 0.0);
+}
+
+#[test]
+fn test_measure_error() {
+	assert!(measure(|_| Err(anyhow::anyhow!("failed"))).is_err());
+}
+
+#[test]
+fn test_baseline() {
+	let result = BenchResult {
+		iterations: 100,
+		mean: 1_500.0,
+		variance: 100.0,
+	};
+	let baseline = BTreeMap::from([(
+		"parse".to_string(),
+		BenchResult {
+			mean: 1_000.0,
+			..result
+		},
+	)]);
+	let benchmarks = Benchmarks::new(baseline);
+	assert!(benchmarks.is_empty());
+	benchmarks.insert("parse", result);
+	benchmarks.insert("draw", result);
+
+	let path = std::env::temp_dir().join(format!("aidoku-bench-{}.json", std::process::id()));
+	benchmarks.save(&path).unwrap();
+	let saved = Benchmarks::with_baseline(&path).unwrap();
+	std::fs::remove_file(&path).unwrap();
+	saved.insert("parse", result);
+	assert!(saved.to_string().contains("+0.00% vs baseline"));
+
+	let output = benchmarks.to_string();
+	let mut lines = output.lines();
+	let draw = lines.next().unwrap();
+	assert!(draw.starts_with("draw"));
+	assert!(!draw.contains("baseline"));
+	assert!(lines.next().unwrap().ends_with("+50.00% vs baseline"));
+}
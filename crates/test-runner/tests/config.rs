@@ -0,0 +1,231 @@
+use aidoku_test_runner::{
+	config::{Config, NetPolicy},
+	libs::{DefaultValue, HttpMethod, NetRequest, NetState, SendError, fixture_etag, parse_range},
+};
+use url::Url;
+
+const CONFIG: &str = r#"
+[net]
+user_agent = "test"
+headers = { Referer = "https://example.com/" }
+timeout = 5.0
+policy = "offline"
+allowed_hosts = ["api.example.com"]
+
+[defaults]
+languages = ["en", "ja"]
+showNsfw = true
+count = 3
+names = { a = "b" }
+
+[clock]
+now = 2024-01-01T00:00:00Z
+timezone = "Asia/Tokyo"
+
+[dirs]
+fixtures = "fixtures"
+"#;
+
+#[test]
+fn test_parse_config() {
+	let config = Config::parse(CONFIG, "/crate").unwrap();
+	assert_eq!(config.net.user_agent.as_deref(), Some("test"));
+	assert_eq!(config.net.timeout, Some(5.0));
+	assert_eq!(config.net.policy, NetPolicy::Offline);
+	assert_eq!(config.now().timestamp(), 1704067200);
+	assert_eq!(config.clock.timezone, Some(chrono_tz::Asia::Tokyo));
+	assert_eq!(
+		config.fixtures_dir().as_deref(),
+		Some(std::path::Path::new("/crate/fixtures"))
+	);
+	assert_eq!(
+		config.snapshots_dir(),
+		std::path::Path::new("/crate/snapshots")
+	);
+
+	let defaults = config.user_defaults().unwrap();
+	assert!(matches!(
+		defaults.get("languages"),
+		Some(DefaultValue::StringArray(languages)) if languages == &["en", "ja"]
+	));
+	assert!(matches!(
+		defaults.get("showNsfw"),
+		Some(DefaultValue::Bool(true))
+	));
+	assert!(matches!(defaults.get("count"), Some(DefaultValue::Int(3))));
+	assert_eq!(
+		defaults.get_map("names"),
+		Some((&["a".to_string()][..], &["b".to_string()][..]))
+	);
+}
+
+#[test]
+fn test_invalid_config() {
+	assert!(Config::parse("[net]\nunknown = 1", "").is_err());
+	assert!(Config::parse("[clock]\ntimezone = \"Nowhere\"", "").is_err());
+	assert!(Config::parse("[defaults]\nvalue = [1, 2]", "").is_err());
+}
+
+#[test]
+fn test_network_policy() {
+	let config = Config::parse(CONFIG, "").unwrap();
+	assert!(
+		config
+			.net
+			.allows(&Url::parse("https://api.example.com/a").unwrap())
+	);
+	assert!(
+		!config
+			.net
+			.allows(&Url::parse("https://example.com/a").unwrap())
+	);
+
+	let mut request = NetRequest::new(HttpMethod::Get);
+	request.url = Some(Url::parse("https://example.com/a").unwrap());
+	assert_eq!(
+		request.send(&config, &NetState::default()),
+		Err(SendError::Blocked)
+	);
+	assert!(
+		Config::default()
+			.net
+			.allows(&Url::parse("https://example.com").unwrap())
+	);
+}
+
+#[test]
+fn test_fixture_responses() {
+	let root = std::env::temp_dir().join(format!("aidoku-config-{}", std::process::id()));
+	std::fs::create_dir_all(root.join("fixtures/example.com/manga")).unwrap();
+	std::fs::write(root.join("fixtures/example.com/manga/1"), "manga").unwrap();
+	std::fs::write(root.join("fixtures/example.com/manga/1%3Fpage=2"), "page 2").unwrap();
+	std::fs::write(root.join("fixtures/example.com/manga/1.post"), "posted").unwrap();
+	std::fs::write(root.join("fixtures/example.com/index.html"), "home").unwrap();
+	std::fs::write(root.join("fixtures/example.com/search%3Fq=a%2Fb"), "search").unwrap();
+	std::fs::write(root.join("fixtures/example.com/search%3Fq=%3F"), "question").unwrap();
+	std::fs::write(
+		root.join("fixtures/example.com/search%3Fq=%253F"),
+		"encoded",
+	)
+	.unwrap();
+	let config = Config::parse(CONFIG, &root).unwrap();
+
+	// fixtures are served even when offline
+	let mut request = NetRequest::new(HttpMethod::Get);
+	request.url = Some(Url::parse("https://example.com/manga/1?page=2").unwrap());
+	request.send(&config, &NetState::default()).unwrap();
+	let response = request.response.unwrap();
+	assert_eq!(response.status, 200);
+	assert_eq!(response.data, b"page 2");
+
+	let fixture = |method, url| config.fixture(method, &Url::parse(url).unwrap());
+	assert_eq!(
+		fixture(HttpMethod::Get, "https://example.com/").as_deref(),
+		Some(&b"home"[..])
+	);
+	assert_eq!(
+		fixture(HttpMethod::Get, "https://example.com/manga/2"),
+		None
+	);
+
+	// characters that can't be in file names are encoded
+	assert_eq!(
+		fixture(HttpMethod::Get, "https://example.com/search?q=a/b").as_deref(),
+		Some(&b"search"[..])
+	);
+
+	// `%` is encoded too, so encoded and decoded queries use different fixtures
+	assert_eq!(
+		fixture(HttpMethod::Get, "https://example.com/search?q=?").as_deref(),
+		Some(&b"question"[..])
+	);
+	assert_eq!(
+		fixture(HttpMethod::Get, "https://example.com/search?q=%3F").as_deref(),
+		Some(&b"encoded"[..])
+	);
+
+	// urls with a query fall back to the fixture without it
+	assert_eq!(
+		fixture(HttpMethod::Get, "https://example.com/manga/1?page=3").as_deref(),
+		Some(&b"manga"[..])
+	);
+	assert_eq!(
+		fixture(HttpMethod::Head, "https://example.com/manga/1").as_deref(),
+		Some(&b"manga"[..])
+	);
+
+	// other methods only use fixtures for the method
+	assert_eq!(
+		fixture(HttpMethod::Post, "https://example.com/manga/1?page=2").as_deref(),
+		Some(&b"posted"[..])
+	);
+	assert_eq!(
+		fixture(HttpMethod::Put, "https://example.com/manga/1"),
+		None
+	);
+
+	// range requests are served as partial content
+	let mut request = NetRequest::new(HttpMethod::Get);
+	request.url = Some(Url::parse("https://example.com/manga/1").unwrap());
+	request
+		.headers
+		.insert("Range", "bytes=1-2".parse().unwrap());
+	request.send(&config, &NetState::default()).unwrap();
+	let response = request.response.unwrap();
+	assert_eq!(response.status, 206);
+	assert_eq!(response.headers["Content-Range"], "bytes 1-2/5");
+	assert_eq!(response.data, b"an");
+
+	// conditional requests for an unchanged fixture are not modified
+	let etag = fixture_etag(b"manga");
+	assert_eq!(response.headers["ETag"], etag.as_str());
+	let mut request = NetRequest::new(HttpMethod::Get);
+	request.url = Some(Url::parse("https://example.com/manga/1").unwrap());
+	request
+		.headers
+		.insert("If-None-Match", etag.parse().unwrap());
+	request
+		.headers
+		.insert("Range", "bytes=1-2".parse().unwrap());
+	request.send(&config, &NetState::default()).unwrap();
+	let response = request.response.unwrap();
+	assert_eq!(response.status, 304);
+	assert_eq!(response.headers["ETag"], etag.as_str());
+	assert!(response.data.is_empty());
+
+	let mut request = NetRequest::new(HttpMethod::Get);
+	request.url = Some(Url::parse("https://example.com/manga/1").unwrap());
+	request
+		.headers
+		.insert("If-None-Match", "\"outdated\"".parse().unwrap());
+	request.send(&config, &NetState::default()).unwrap();
+	let response = request.response.unwrap();
+	assert_eq!(response.status, 200);
+	assert_eq!(response.data, b"manga");
+
+	std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_parse_range() {
+	assert_eq!(parse_range("bytes=0-99", 1000), Some((0, 99)));
+	assert_eq!(parse_range("bytes=900-", 1000), Some((900, 999)));
+	assert_eq!(parse_range("bytes=-100", 1000), Some((900, 999)));
+	assert_eq!(parse_range("bytes=-2000", 1000), Some((0, 999)));
+	assert_eq!(parse_range("bytes=500-2000", 1000), Some((500, 999)));
+	assert_eq!(parse_range("bytes=1000-", 1000), None);
+	assert_eq!(parse_range("bytes=5-1", 1000), None);
+	assert_eq!(parse_range("items=0-1", 1000), None);
+}
+
+#[test]
+fn test_background_send() {
+	let config = std::sync::Arc::new(Config::parse(CONFIG, "").unwrap());
+	let mut request = NetRequest::new(HttpMethod::Get);
+	request.url = Some(Url::parse("https://example.com/a").unwrap());
+	let pending = request.send_in_background(config, std::sync::Arc::default());
+	let (response, result) = pending.join().unwrap();
+	assert!(response.is_none());
+	assert_eq!(result, Err(SendError::Blocked));
+	assert!(request.response.is_none());
+}
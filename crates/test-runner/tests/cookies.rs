@@ -0,0 +1,108 @@
+use aidoku_test_runner::libs::{Cookie, CookieStore};
+use reqwest::header::{HeaderMap, HeaderValue, SET_COOKIE};
+use url::Url;
+
+fn cookie(name: &str, value: &str, domain: &str) -> Cookie {
+	Cookie {
+		name: name.into(),
+		value: value.into(),
+		expires_date: None,
+		domain: domain.into(),
+		path: "/".into(),
+		is_secure: false,
+		is_http_only: false,
+	}
+}
+
+#[test]
+fn test_parse() {
+	let url = Url::parse("https://www.example.com/manga/1").unwrap();
+	let cookie = Cookie::parse(
+		"session=abc; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Path=/manga; Secure",
+		&url,
+		0,
+	)
+	.unwrap();
+	assert_eq!(cookie.name, "session");
+	assert_eq!(cookie.domain, "www.example.com");
+	assert_eq!(cookie.path, "/manga");
+	assert_eq!(cookie.expires_date, Some(1445412480));
+	assert!(cookie.is_secure);
+
+	let cookie = Cookie::parse("a=b; Domain=.example.com; Max-Age=10", &url, 100).unwrap();
+	assert_eq!(cookie.domain, "example.com");
+	assert_eq!(cookie.expires_date, Some(110));
+
+	// domains that the response's host isn't part of are rejected
+	assert_eq!(Cookie::parse("a=b; Domain=other.com", &url, 0), None);
+	assert_eq!(Cookie::parse("a=b; Domain=ww.example.com", &url, 0), None);
+	assert_eq!(
+		Cookie::parse("a=b; Domain=sub.www.example.com", &url, 0),
+		None
+	);
+	assert_eq!(Cookie::parse("invalid", &url, 0), None);
+}
+
+#[test]
+fn test_store() {
+	let mut store = CookieStore::new();
+	store.set(cookie("a", "1", "example.com"), 0);
+	store.set(cookie("b", "2", "www.example.com"), 0);
+	store.set(cookie("c", "3", "other.com"), 0);
+	store.set(cookie("a", "4", "example.com"), 0);
+
+	let names = |cookies: Vec<Cookie>| {
+		cookies
+			.into_iter()
+			.map(|cookie| format!("{}={}", cookie.name, cookie.value))
+			.collect::<Vec<_>>()
+	};
+	assert_eq!(names(store.get("example.com", 0)), ["a=4"]);
+	assert_eq!(names(store.get("www.example.com", 0)), ["b=2", "a=4"]);
+	assert_eq!(names(store.get("notexample.com", 0)), Vec::<String>::new());
+
+	store.delete(Some("b"), "www.example.com");
+	assert_eq!(names(store.get("www.example.com", 0)), ["a=4"]);
+
+	// deleting for a subdomain keeps the cookies of its parent domain
+	store.set(cookie("b", "5", "www.example.com"), 0);
+	store.delete(Some("a"), "www.example.com");
+	store.delete(None, "www.example.com");
+	assert_eq!(names(store.get("www.example.com", 0)), ["a=4"]);
+
+	// deleting for a domain removes the cookies of its subdomains
+	store.set(cookie("b", "6", "www.example.com"), 0);
+	store.delete(None, "example.com");
+	assert!(store.get("www.example.com", 0).is_empty());
+	assert_eq!(names(store.get("other.com", 0)), ["c=3"]);
+
+	// expired cookies aren't returned
+	let mut expired = cookie("d", "5", "other.com");
+	expired.expires_date = Some(10);
+	store.set(expired, 0);
+	assert_eq!(store.get("other.com", 5).len(), 2);
+	assert_eq!(store.get("other.com", 10).len(), 1);
+}
+
+#[test]
+fn test_request_header() {
+	let mut store = CookieStore::new();
+	let url = Url::parse("https://example.com/manga/").unwrap();
+	let mut headers = HeaderMap::new();
+	headers.append(SET_COOKIE, HeaderValue::from_static("a=1"));
+	headers.append(SET_COOKIE, HeaderValue::from_static("b=2; Path=/manga"));
+	headers.append(SET_COOKIE, HeaderValue::from_static("c=3; Secure"));
+	store.store_response(&url, &headers, 0);
+
+	let header = |url: &str| store.header(&Url::parse(url).unwrap(), 0);
+	assert_eq!(
+		header("https://example.com/manga/1").as_deref(),
+		Some("a=1; b=2; c=3")
+	);
+	assert_eq!(header("http://example.com/").as_deref(), Some("a=1"));
+	assert_eq!(
+		header("https://example.com/mangas").as_deref(),
+		Some("a=1; c=3")
+	);
+	assert_eq!(header("https://other.com/"), None);
+}
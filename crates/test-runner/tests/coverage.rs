@@ -0,0 +1,43 @@
+use aidoku_test_runner::coverage::Coverage;
+use std::sync::Arc;
+use wasmer::{Instance, Module, imports, wat2wasm};
+
+const MODULE: &str = r#"
+(module
+	(func (export "sum") (param $n i32) (result i32)
+		(local $total i32)
+		(block $done
+			(loop $next
+				(br_if $done (i32.eqz (local.get $n)))
+				(local.set $total (i32.add (local.get $total) (local.get $n)))
+				(local.set $n (i32.sub (local.get $n) (i32.const 1)))
+				(br $next)))
+		(local.get $total))
+	(func (export "unused") (result i32)
+		(i32.const 1)))
+"#;
+
+#[test]
+fn test_coverage_counts_blocks() {
+	let wasm = wat2wasm(MODULE.as_bytes()).unwrap();
+	let coverage = Arc::new(Coverage::new(&wasm).unwrap());
+	assert!(!coverage.has_line_info());
+
+	let mut store = coverage.store();
+	let module = Module::new(&store, &wasm).unwrap();
+	let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+	let sum = instance
+		.exports
+		.get_typed_function::<i32, i32>(&store, "sum")
+		.unwrap();
+	// instrumentation shouldn't change behavior
+	assert_eq!(sum.call(&mut store, 4).unwrap(), 10);
+	coverage.collect(&mut store, &instance);
+
+	let hits = coverage.hits();
+	// entry block runs once, and the loop body runs once per iteration plus the exit check
+	assert_eq!(hits.first(), Some(&1));
+	assert!(hits.contains(&5));
+	// the unused function is never hit
+	assert_eq!(hits.last(), Some(&0));
+}
@@ -30,3 +30,78 @@ fn test_select_root_matches() {
 	let element = ElementRef::wrap(node).unwrap();
 	assert_eq!(element.value().id(), Some("root"));
 }
+
+fn first_attr(html: &HtmlDocument, selector: &str, name: &str) -> Option<String> {
+	let selector = Selector::parse(selector).unwrap();
+	html.select(&selector).0.first()?.attr(name)
+}
+
+#[test]
+fn test_abs_attr() {
+	let html = HtmlDocument::parse(
+		r#"
+		<a id="relative" href="../b?c=1">b</a>
+		<a id="absolute" href="https://other.com/d">d</a>
+		<a id="protocol" href="//cdn.example.com/e.jpg">e</a>
+		<a id="missing">f</a>
+		"#,
+		Some("https://example.com/a/page"),
+	);
+	assert_eq!(
+		first_attr(&html, "#relative", "abs:href").as_deref(),
+		Some("https://example.com/b?c=1")
+	);
+	assert_eq!(
+		first_attr(&html, "#relative", "href").as_deref(),
+		Some("../b?c=1")
+	);
+	assert_eq!(
+		first_attr(&html, "#absolute", "abs:href").as_deref(),
+		Some("https://other.com/d")
+	);
+	assert_eq!(
+		first_attr(&html, "#protocol", "abs:href").as_deref(),
+		Some("https://cdn.example.com/e.jpg")
+	);
+	assert_eq!(first_attr(&html, "#missing", "abs:href"), None);
+}
+
+#[test]
+fn test_abs_attr_base_href() {
+	let html = HtmlDocument::parse(
+		r#"
+		<head><base href="/static/"></head>
+		<body><img src="cover.jpg"></body>
+		"#,
+		Some("https://example.com/manga/1"),
+	);
+	assert_eq!(
+		html.base_uri.as_deref().map(|url| url.as_str()),
+		Some("https://example.com/static/")
+	);
+	assert_eq!(
+		first_attr(&html, "img", "abs:src").as_deref(),
+		Some("https://example.com/static/cover.jpg")
+	);
+
+	// an absolute base is used even without a document url
+	let html = HtmlDocument::parse(
+		r#"<base href="https://example.com/"><img src="cover.jpg">"#,
+		None,
+	);
+	assert_eq!(
+		first_attr(&html, "img", "abs:src").as_deref(),
+		Some("https://example.com/cover.jpg")
+	);
+}
+
+#[test]
+fn test_abs_attr_without_base() {
+	let html = HtmlDocument::parse(r#"<img src="/cover.jpg">"#, None);
+	// relative urls can't be resolved without a base
+	assert_eq!(first_attr(&html, "img", "abs:src"), None);
+	assert_eq!(
+		first_attr(&html, "img", "src").as_deref(),
+		Some("/cover.jpg")
+	);
+}
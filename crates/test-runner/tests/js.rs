@@ -0,0 +1,100 @@
+use aidoku_test_runner::libs::JsContext;
+
+fn eval_async(js: &str) -> Option<String> {
+	let mut context = JsContext::new();
+	let result = context.eval_async(js).ok()?;
+	context.to_string(&result)
+}
+
+#[test]
+fn test_eval_async_resolves_promises() {
+	assert_eq!(
+		eval_async("(async () => { const a = await Promise.resolve(1); return a + 1; })()"),
+		Some("2".into())
+	);
+	assert_eq!(
+		eval_async("Promise.resolve('a').then((a) => a + 'b')"),
+		Some("ab".into())
+	);
+	// non-promise values are returned as-is
+	assert_eq!(eval_async("1 + 2"), Some("3".into()));
+}
+
+#[test]
+fn test_eval_async_rejection() {
+	let mut context = JsContext::new();
+	let err = context
+		.eval_async("(async () => { throw new Error('failed'); })()")
+		.expect_err("promise should reject");
+	assert!(err.to_string().contains("failed"));
+}
+
+#[test]
+fn test_eval_async_timers() {
+	assert_eq!(
+		eval_async(
+			r#"
+			const start = Date.now();
+			new Promise((resolve) => {
+				const order = [];
+				setTimeout(() => order.push('b'), 200);
+				setTimeout(() => order.push('a'), 100);
+				const cancelled = setTimeout(() => order.push('x'), 150);
+				clearTimeout(cancelled);
+				let count = 0;
+				const interval = setInterval(() => {
+					count += 1;
+					if (count == 3) {
+						clearInterval(interval);
+						resolve(order.join('') + (Date.now() - start));
+					}
+				}, 100);
+			})
+			"#
+		),
+		Some("ab300".into())
+	);
+}
+
+#[test]
+fn test_console_output() {
+	let mut context = JsContext::new();
+	context
+		.eval(r#"console.log("a", 1, { b: [2] }); console.warn("c")"#)
+		.expect("eval failed");
+	assert_eq!(context.take_console_output(), "a 1 {\"b\":[2]}\n[warn] c\n");
+	assert_eq!(context.take_console_output(), "");
+}
+
+#[test]
+fn test_browser_globals() {
+	let mut context = JsContext::new();
+	let mut eval = |js: &str| {
+		let result = context.eval(js).expect("eval failed");
+		context.to_string(&result).unwrap()
+	};
+	assert_eq!(eval("btoa('hello world')"), "aGVsbG8gd29ybGQ=");
+	assert_eq!(eval("atob('aGVsbG8gd29ybGQ=')"), "hello world");
+	assert_eq!(eval("atob(btoa('ab'))"), "ab");
+	assert_eq!(
+		eval("new TextDecoder().decode(new TextEncoder().encode('héllo 😀'))"),
+		"héllo 😀"
+	);
+	assert_eq!(eval("new TextEncoder().encode('é').length"), "2");
+	// views only decode their own bytes
+	assert_eq!(
+		eval("new TextDecoder().decode(new TextEncoder().encode('abcdefgh').subarray(4, 8))"),
+		"efgh"
+	);
+	assert_eq!(
+		eval(
+			"new TextDecoder().decode(new DataView(new TextEncoder().encode('abcdef').buffer, 1, 3))"
+		),
+		"bcd"
+	);
+	assert_eq!(
+		eval("typeof window.document + typeof self.navigator.userAgent"),
+		"objectstring"
+	);
+	assert_eq!(eval("window === globalThis"), "true");
+}
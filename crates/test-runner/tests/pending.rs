@@ -0,0 +1,111 @@
+use aidoku_test_runner::{WasmEnv, config::Config, imports::generate_imports};
+use std::sync::Arc;
+use wasmer::{FunctionEnv, Instance, Module, Store, TypedFunction, wat2wasm};
+
+const MODULE: &str = r#"
+(module
+	(import "net" "init" (func $init (param i32) (result i32)))
+	(import "net" "set_url" (func $set_url (param i32 i32 i32) (result i32)))
+	(import "net" "start" (func $start (param i32) (result i32)))
+	(import "net" "poll" (func $poll (param i32) (result i32)))
+	(import "net" "wait" (func $wait (param i32) (result i32)))
+	(import "net" "wait_any" (func $wait_any (param i32 i32) (result i32)))
+	(import "net" "get_status_code" (func $get_status_code (param i32) (result i32)))
+	(import "std" "destroy" (func $destroy (param i32)))
+	(memory (export "memory") 1)
+	(data (i32.const 0) "https://example.com/manga/1")
+	(func (export "request") (result i32)
+		(local $rid i32)
+		(local.set $rid (call $init (i32.const 0)))
+		(drop (call $set_url (local.get $rid) (i32.const 0) (i32.const 27)))
+		(local.get $rid))
+	(func (export "start") (param i32) (result i32) (call $start (local.get 0)))
+	(func (export "poll") (param i32) (result i32) (call $poll (local.get 0)))
+	(func (export "wait") (param i32) (result i32) (call $wait (local.get 0)))
+	(func (export "wait_any") (param i32 i32) (result i32)
+		(call $wait_any (local.get 0) (local.get 1)))
+	(func (export "status") (param i32) (result i32) (call $get_status_code (local.get 0)))
+	(func (export "destroy") (param i32) (call $destroy (local.get 0))))
+"#;
+
+/// The offset in memory where request ids are written for `wait_any`.
+const RIDS_PTR: i32 = 64;
+
+#[test]
+fn test_pending_requests() {
+	let root = std::env::temp_dir().join(format!("aidoku-pending-{}", std::process::id()));
+	std::fs::create_dir_all(root.join("fixtures/example.com/manga")).unwrap();
+	std::fs::write(root.join("fixtures/example.com/manga/1"), "manga").unwrap();
+	let config = Config::parse("[dirs]\nfixtures = \"fixtures\"", &root).unwrap();
+
+	let wasm = wat2wasm(MODULE.as_bytes()).unwrap();
+	let mut store = Store::default();
+	let module = Module::new(&store, &wasm).unwrap();
+	let env = FunctionEnv::new(&mut store, WasmEnv::with_config(Arc::new(config)).unwrap());
+	let imports = generate_imports(&mut store, &env);
+	let instance = Instance::new(&mut store, &module, &imports).unwrap();
+	let memory = instance.exports.get_memory("memory").unwrap().clone();
+	env.as_mut(&mut store).memory = Some(memory.clone());
+
+	let function = |name: &str| -> TypedFunction<i32, i32> {
+		instance.exports.get_typed_function(&store, name).unwrap()
+	};
+	let request: TypedFunction<(), i32> = instance
+		.exports
+		.get_typed_function(&store, "request")
+		.unwrap();
+	let wait_any: TypedFunction<(i32, i32), i32> = instance
+		.exports
+		.get_typed_function(&store, "wait_any")
+		.unwrap();
+	let destroy: TypedFunction<i32, ()> = instance
+		.exports
+		.get_typed_function(&store, "destroy")
+		.unwrap();
+	let (start, poll, wait, status) = (
+		function("start"),
+		function("poll"),
+		function("wait"),
+		function("status"),
+	);
+
+	// a started request can be waited on
+	let rid = request.call(&mut store).unwrap();
+	assert_eq!(start.call(&mut store, rid).unwrap(), 0);
+	assert!(matches!(poll.call(&mut store, rid).unwrap(), 0 | 1));
+	assert_eq!(wait.call(&mut store, rid).unwrap(), 0);
+	assert_eq!(status.call(&mut store, rid).unwrap(), 200);
+	assert!(env.as_ref(&store).pending.is_empty());
+
+	// requests that weren't started are finished, but can't be waited on
+	assert_eq!(poll.call(&mut store, rid).unwrap(), 1);
+	assert_eq!(wait.call(&mut store, rid).unwrap(), -8);
+	assert_eq!(poll.call(&mut store, 9999).unwrap(), -1);
+
+	// waiting for any request returns the index of a finished one
+	let rids = [
+		request.call(&mut store).unwrap(),
+		request.call(&mut store).unwrap(),
+	];
+	for rid in rids {
+		assert_eq!(start.call(&mut store, rid).unwrap(), 0);
+	}
+	let bytes: Vec<u8> = rids.iter().flat_map(|rid| rid.to_le_bytes()).collect();
+	memory.view(&store).write(RIDS_PTR as u64, &bytes).unwrap();
+	let idx = wait_any.call(&mut store, RIDS_PTR, 2).unwrap();
+	assert!(matches!(idx, 0 | 1));
+	assert_eq!(poll.call(&mut store, rids[idx as usize]).unwrap(), 1);
+	for rid in rids {
+		assert_eq!(wait.call(&mut store, rid).unwrap(), 0);
+	}
+	assert_eq!(wait_any.call(&mut store, RIDS_PTR, 0).unwrap(), -1);
+
+	// destroying a request that wasn't waited on forgets it
+	let rid = request.call(&mut store).unwrap();
+	assert_eq!(start.call(&mut store, rid).unwrap(), 0);
+	assert_eq!(env.as_ref(&store).pending.len(), 1);
+	destroy.call(&mut store, rid).unwrap();
+	assert!(env.as_ref(&store).pending.is_empty());
+
+	std::fs::remove_dir_all(&root).unwrap();
+}
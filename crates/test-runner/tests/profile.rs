@@ -0,0 +1,40 @@
+use aidoku_test_runner::{WasmEnv, imports::generate_imports, profile::profile_imports};
+use wasmer::{FunctionEnv, Instance, Module, Store, wat2wasm};
+
+const MODULE: &str = r#"
+(module
+	(import "env" "print" (func $print (param i32 i32)))
+	(memory (export "memory") 1)
+	(data (i32.const 0) "hello")
+	(func (export "run")
+		(call $print (i32.const 0) (i32.const 5))
+		(call $print (i32.const 0) (i32.const 2))))
+"#;
+
+#[test]
+fn test_profile_imports() {
+	let wasm = wat2wasm(MODULE.as_bytes()).unwrap();
+	let mut store = Store::default();
+	let module = Module::new(&store, &wasm).unwrap();
+	let env = FunctionEnv::new(&mut store, WasmEnv::new());
+	let imports = generate_imports(&mut store, &env);
+	let imports = profile_imports(&mut store, &env, &imports);
+	let instance = Instance::new(&mut store, &module, &imports).unwrap();
+	env.as_mut(&mut store).memory = Some(instance.exports.get_memory("memory").unwrap().clone());
+
+	let run = instance
+		.exports
+		.get_typed_function::<(), ()>(&store, "run")
+		.unwrap();
+	run.call(&mut store).unwrap();
+
+	let env = env.as_ref(&store);
+	assert_eq!(env.stdout, "hello\nhe\n");
+	let profile = env.profile.as_ref().expect("profile missing");
+	let stats = profile.imports["env::print"];
+	assert_eq!(stats.calls, 2);
+	assert_eq!(stats.bytes_in, 7);
+	assert_eq!(stats.bytes_out, 0);
+	assert_eq!(profile.total().calls, 2);
+	assert!(profile.to_string().contains("env::print"));
+}
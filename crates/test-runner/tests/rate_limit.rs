@@ -0,0 +1,91 @@
+use aidoku_test_runner::libs::{RateLimit, RateLimits};
+use std::time::{Duration, Instant};
+use url::Url;
+
+fn url(url: &str) -> Url {
+	Url::parse(url).unwrap()
+}
+
+#[test]
+fn test_rate_limit() {
+	let now = Instant::now();
+	let mut limit = RateLimit::new(2, Duration::from_secs(10));
+	assert_eq!(limit.delay(now), Duration::ZERO);
+	limit.record(now);
+	limit.record(now + Duration::from_secs(1));
+	assert_eq!(
+		limit.delay(now + Duration::from_secs(2)),
+		Duration::from_secs(8)
+	);
+	assert_eq!(limit.delay(now + Duration::from_secs(10)), Duration::ZERO);
+
+	assert!(RateLimit::from_ffi(0, 1, 0).is_none());
+	assert!(RateLimit::from_ffi(1, 1, 3).is_none());
+	assert!(RateLimit::from_ffi(1, -1, 0).is_none());
+	assert!(RateLimit::from_ffi(-1, 1, 0).is_none());
+	let mut limit = RateLimit::from_ffi(1, 2, 1).unwrap();
+	limit.record(now);
+	assert_eq!(limit.delay(now), Duration::from_secs(120));
+}
+
+#[test]
+fn test_host_rate_limits() {
+	let now = Instant::now();
+	let mut limits = RateLimits::new();
+	limits.set("api.example.com", RateLimit::from_ffi(1, 10, 0));
+	limits.set("*.cdn.example.com", RateLimit::from_ffi(1, 20, 0));
+	limits.set("https://api.example.com/v2/", RateLimit::from_ffi(1, 30, 0));
+
+	let api = url("https://api.example.com/v1/manga");
+	assert_eq!(limits.reserve(&api, now), Duration::ZERO);
+	assert_eq!(limits.reserve(&api, now), Duration::from_secs(10));
+
+	// the url prefix is more specific than the host
+	let v2 = url("https://api.example.com/v2/manga");
+	assert_eq!(limits.reserve(&v2, now), Duration::ZERO);
+	assert_eq!(limits.reserve(&v2, now), Duration::from_secs(30));
+
+	let cdn = url("https://img1.cdn.example.com/page.png");
+	assert_eq!(limits.reserve(&cdn, now), Duration::ZERO);
+	assert_eq!(limits.reserve(&cdn, now), Duration::from_secs(20));
+
+	// other hosts aren't limited
+	let other = url("https://example.com/");
+	assert_eq!(limits.reserve(&other, now), Duration::ZERO);
+	assert_eq!(limits.reserve(&other, now), Duration::ZERO);
+
+	// removing a limit
+	limits.set("api.example.com", None);
+	assert_eq!(limits.reserve(&api, now), Duration::ZERO);
+}
+
+#[test]
+fn test_url_prefix_case() {
+	let now = Instant::now();
+	let mut limits = RateLimits::new();
+	// only the scheme and host of a url prefix are case insensitive
+	limits.set("HTTPS://Example.com/API/", RateLimit::from_ffi(1, 10, 0));
+
+	let api = url("https://example.COM/API/manga");
+	assert_eq!(limits.reserve(&api, now), Duration::ZERO);
+	assert_eq!(limits.reserve(&api, now), Duration::from_secs(10));
+
+	let lowercase = url("https://example.com/api/manga");
+	assert_eq!(limits.reserve(&lowercase, now), Duration::ZERO);
+	assert_eq!(limits.reserve(&lowercase, now), Duration::ZERO);
+}
+
+#[test]
+fn test_global_rate_limit() {
+	let now = Instant::now();
+	let mut limits = RateLimits::new();
+	limits.set_global(RateLimit::from_ffi(2, 10, 0));
+	limits.set("example.com", RateLimit::from_ffi(1, 5, 0));
+
+	let example = url("https://example.com/");
+	let other = url("https://other.com/");
+	assert_eq!(limits.reserve(&example, now), Duration::ZERO);
+	assert_eq!(limits.reserve(&other, now), Duration::ZERO);
+	// both limits apply, and the longer wait is used
+	assert_eq!(limits.reserve(&example, now), Duration::from_secs(10));
+}
@@ -0,0 +1,113 @@
+use aidoku_test_runner::{
+	config::Config,
+	libs::{HttpMethod, NetRequest, NetState, SendError},
+};
+use std::{
+	io::{BufRead, BufReader, Write},
+	net::TcpListener,
+};
+use url::Url;
+
+/// Starts a local server where `/n` redirects to `/n+1` until `/3`, which responds with a body,
+/// and `/external` redirects to another host.
+fn serve_redirects() -> Url {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let base = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+	std::thread::spawn(move || {
+		for mut stream in listener.incoming().flatten() {
+			let mut reader = BufReader::new(&stream);
+			let mut request_line = String::new();
+			reader.read_line(&mut request_line).unwrap();
+			// read the rest of the request headers
+			let mut line = String::new();
+			while reader.read_line(&mut line).is_ok_and(|len| len > 2) {
+				line.clear();
+			}
+			let path = request_line.split(' ').nth(1).unwrap_or_default();
+			let page = path.trim_start_matches('/').parse::<i32>().unwrap_or(0);
+			let response = if path == "/external" {
+				"HTTP/1.1 302 Found\r\nLocation: http://example.invalid/\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".into()
+			} else if page < 3 {
+				format!(
+					"HTTP/1.1 302 Found\r\nLocation: /{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+					page + 1
+				)
+			} else {
+				"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\ndone".into()
+			};
+			stream.write_all(response.as_bytes()).unwrap();
+		}
+	});
+	base
+}
+
+fn send(url: Url, max_redirects: Option<usize>) -> NetRequest {
+	let mut request = NetRequest::new(HttpMethod::Get);
+	request.url = Some(url);
+	request.max_redirects = max_redirects;
+	request
+		.send(&Config::default(), &NetState::default())
+		.unwrap();
+	request
+}
+
+#[test]
+fn test_follow_redirects() {
+	let base = serve_redirects();
+	let response = send(base.join("1").unwrap(), None).response.unwrap();
+	assert_eq!(response.status, 200);
+	assert_eq!(response.data, b"done");
+	assert_eq!(response.url, base.join("3").unwrap());
+	assert_eq!(
+		response.redirects,
+		[base.join("2").unwrap(), base.join("3").unwrap()]
+	);
+}
+
+#[test]
+fn test_limit_redirects() {
+	let base = serve_redirects();
+
+	// the redirect response is returned when not following redirects
+	let response = send(base.join("1").unwrap(), Some(0)).response.unwrap();
+	assert_eq!(response.status, 302);
+	assert_eq!(response.headers["Location"], "/2");
+	assert!(response.redirects.is_empty());
+
+	let response = send(base.join("1").unwrap(), Some(1)).response.unwrap();
+	assert_eq!(response.status, 302);
+	assert_eq!(response.url, base.join("2").unwrap());
+	assert_eq!(response.redirects, [base.join("2").unwrap()]);
+
+	// the default limit is an error when exceeded
+	let mut request = NetRequest::new(HttpMethod::Get);
+	request.url = Some(base.join("-20").unwrap());
+	assert_eq!(
+		request.send(&Config::default(), &NetState::default()),
+		Err(SendError::RequestError)
+	);
+}
+
+#[test]
+fn test_blocked_redirect() {
+	let base = serve_redirects();
+	let config = Config::parse(
+		"[net]\npolicy = \"offline\"\nallowed_hosts = [\"127.0.0.1\"]",
+		"",
+	)
+	.unwrap();
+
+	// redirects between allowed hosts are followed
+	let mut request = NetRequest::new(HttpMethod::Get);
+	request.url = Some(base.join("2").unwrap());
+	request.send(&config, &NetState::default()).unwrap();
+	assert_eq!(request.response.unwrap().data, b"done");
+
+	// redirects to other hosts are blocked
+	let mut request = NetRequest::new(HttpMethod::Get);
+	request.url = Some(base.join("external").unwrap());
+	assert_eq!(
+		request.send(&config, &NetState::default()),
+		Err(SendError::Blocked)
+	);
+}
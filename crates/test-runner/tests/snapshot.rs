@@ -0,0 +1,59 @@
+use aidoku_test_runner::libs::{SnapshotResult, Snapshots};
+use serde_json::json;
+
+fn snapshots(name: &str) -> Snapshots {
+	let dir = std::env::temp_dir().join(format!("aidoku-snapshots-{name}-{}", std::process::id()));
+	let _ = std::fs::remove_dir_all(&dir);
+	Snapshots::new(dir)
+}
+
+#[test]
+fn test_snapshot_path() {
+	let snapshots = Snapshots::new("snapshots");
+	assert_eq!(
+		snapshots.path("tests::search", "page/1"),
+		std::path::Path::new("snapshots/tests__search__page_1.json")
+	);
+}
+
+#[test]
+fn test_snapshot_create_and_match() {
+	let snapshots = snapshots("match");
+	let value = json!({ "title": "a", "tags": ["b"] });
+
+	let result = snapshots.assert("test", "manga", &value).unwrap();
+	assert_eq!(result, SnapshotResult::Created);
+	let stored = std::fs::read_to_string(snapshots.path("test", "manga")).unwrap();
+	assert_eq!(
+		stored,
+		"{\n  \"title\": \"a\",\n  \"tags\": [\n    \"b\"\n  ]\n}\n"
+	);
+
+	let result = snapshots.assert("test", "manga", &value).unwrap();
+	assert_eq!(result, SnapshotResult::Matched);
+	std::fs::remove_dir_all(&snapshots.dir).unwrap();
+}
+
+#[test]
+fn test_snapshot_mismatch() {
+	let mut snapshots = snapshots("mismatch");
+	snapshots
+		.assert("test", "manga", &json!({ "title": "a" }))
+		.unwrap();
+
+	let new = json!({ "title": "b" });
+	let SnapshotResult::Mismatched(diff) = snapshots.assert("test", "manga", &new).unwrap() else {
+		panic!("snapshot should not match");
+	};
+	assert!(diff.contains("-  \"title\": \"a\""));
+	assert!(diff.contains("+  \"title\": \"b\""));
+
+	// mismatched snapshots are overwritten when updating
+	snapshots.update = true;
+	let result = snapshots.assert("test", "manga", &new).unwrap();
+	assert_eq!(result, SnapshotResult::Updated);
+	snapshots.update = false;
+	let result = snapshots.assert("test", "manga", &new).unwrap();
+	assert_eq!(result, SnapshotResult::Matched);
+	std::fs::remove_dir_all(&snapshots.dir).unwrap();
+}
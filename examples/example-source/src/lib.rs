@@ -477,4 +477,138 @@ mod test {
 		let result = context.eval("1 + 2");
 		assert_eq!(result, Ok(String::from("3")));
 	}
+
+	#[aidoku_test]
+	fn test_from_element() {
+		// structs can be extracted from html elements with the aidoku-derive crate
+		use aidoku::imports::html::{Html, HtmlError};
+		use aidoku_derive::FromElement;
+
+		#[derive(Debug, PartialEq, FromElement)]
+		struct ChapterItem {
+			#[html(attr = "data-id", parse)]
+			id: u32,
+			#[html(select = "a", attr = "href")]
+			url: String,
+			#[html(own_text, trim)]
+			title: String,
+		}
+
+		#[derive(Debug, PartialEq, FromElement)]
+		struct MangaItem {
+			#[html(select = ".title", trim)]
+			title: String,
+			#[html(select = ".description")]
+			description: Option<String>,
+			#[html(select = ".tags a")]
+			tags: Vec<String>,
+			#[html(select = ".rating", parse)]
+			rating: Option<f32>,
+			#[html(select = ".chapter", nested)]
+			chapters: Vec<ChapterItem>,
+			#[html(skip)]
+			key: String,
+		}
+
+		let html = Html::parse(
+			r#"<div class="manga">
+				<h1 class="title">  Example  </h1>
+				<span class="tags"><a>Action</a><a>Comedy</a></span>
+				<span class="rating"> 4.5 </span>
+				<div class="chapter" data-id="2"><a href="/chapter/2">Read</a> Chapter 2 </div>
+				<div class="chapter" data-id="1"><a href="/chapter/1">Read</a> Chapter 1 </div>
+			</div>
+			<div class="empty"><h1 class="title">Empty</h1><span class="rating">-</span></div>"#,
+		)
+		.unwrap();
+
+		let manga = html.select_first(".manga").unwrap();
+		assert_eq!(
+			manga.extract::<MangaItem>(),
+			Ok(MangaItem {
+				title: "Example".into(),
+				description: None,
+				tags: vec!["Action".into(), "Comedy".into()],
+				rating: Some(4.5),
+				chapters: vec![
+					ChapterItem {
+						id: 2,
+						url: "/chapter/2".into(),
+						title: "Chapter 2".into(),
+					},
+					ChapterItem {
+						id: 1,
+						url: "/chapter/1".into(),
+						title: "Chapter 1".into(),
+					},
+				],
+				key: String::new(),
+			})
+		);
+
+		// optional values that fail to parse are still errors
+		let empty = html.select_first(".empty").unwrap();
+		assert_eq!(
+			empty.extract::<MangaItem>(),
+			Err(HtmlError::InvalidValue(".rating".into()))
+		);
+
+		#[derive(Debug, FromElement)]
+		struct MissingElement {
+			#[html(select = ".missing")]
+			_value: String,
+		}
+		assert_eq!(
+			manga.extract::<MissingElement>().unwrap_err(),
+			HtmlError::MissingElement(".missing".into())
+		);
+
+		#[derive(Debug, FromElement)]
+		struct MissingValue {
+			#[html(select = ".title", attr = "href")]
+			_value: String,
+		}
+		assert_eq!(
+			manga.extract::<MissingValue>().unwrap_err(),
+			HtmlError::MissingValue(".title[href]".into())
+		);
+
+		#[derive(Debug, FromElement)]
+		struct InvalidValue {
+			#[html(select = ".title", parse)]
+			_value: i32,
+		}
+		assert_eq!(
+			manga.extract::<InvalidValue>().unwrap_err(),
+			HtmlError::InvalidValue(".title".into())
+		);
+	}
+
+	#[aidoku_test]
+	fn test_http_cache() {
+		use aidoku::imports::net::{Request, clear_cache, enable_cache};
+		enable_cache(1024 * 1024);
+		clear_cache();
+		// the fixture is served with an etag, and a 304 when If-None-Match matches it
+		let url = "https://example.com/cache";
+		let response = Request::get(url).unwrap().send().unwrap();
+		let etag = response.get_header("ETag").unwrap();
+		let first = response.get_string().unwrap();
+
+		// the second request is conditional, and is served from the cache
+		let response = Request::get(url).unwrap().send().unwrap();
+		assert_eq!(response.status_code(), 200);
+		assert_eq!(response.get_string().unwrap(), first);
+
+		// requests that opt out get the server's not modified response as is
+		let response = Request::get(url)
+			.unwrap()
+			.cache(false)
+			.header("If-None-Match", &etag)
+			.send()
+			.unwrap();
+		assert_eq!(response.status_code(), 304);
+		assert!(response.get_data().unwrap().is_empty());
+		clear_cache();
+	}
 }